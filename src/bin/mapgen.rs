@@ -0,0 +1,93 @@
+use std::process::ExitCode;
+
+use snake::generator::{generate, Layout};
+
+const USAGE: &str = "Usage: mapgen <maze|obstacles|symmetric> [--seed N] [--density PERCENT] \
+                     [--size WIDTHxHEIGHT] [--name NAME] [--output FILE]";
+
+///
+/// Options of the command line
+///
+struct Options {
+    layout: String,         // Kind of map
+    seed: u64,              // Same seed, same map
+    density: u32,           // Obstacles in percent of the grid
+    size: (u32, u32),       // Grid size, in blocks
+    name: Option<String>,   // Name of the map, the layout and seed by default
+    output: Option<String>, // Map file to write, the standard output by default
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        layout: args.next().ok_or("Missing layout")?,
+        seed: 0,
+        density: 15,
+        size: (40, 30),
+        name: None,
+        output: None,
+    };
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", arg))?;
+        let invalid = |e: std::num::ParseIntError| format!("Invalid {} {}: {}", arg, value, e);
+        match arg.as_str() {
+            "--seed" => options.seed = value.parse().map_err(invalid)?,
+            "--density" => options.density = value.parse().map_err(invalid)?,
+            "--size" => {
+                let (width, height) = value
+                    .split_once('x')
+                    .ok_or_else(|| format!("Invalid size {}", value))?;
+                options.size = (
+                    width.parse().map_err(invalid)?,
+                    height.parse().map_err(invalid)?,
+                );
+            }
+            "--name" => options.name = Some(value),
+            "--output" => options.output = Some(value),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args(std::env::args().skip(1))?;
+    let layout = match options.layout.as_str() {
+        "maze" => Layout::Maze,
+        "obstacles" => Layout::Obstacles {
+            density: options.density,
+        },
+        "symmetric" => Layout::Symmetric {
+            density: options.density,
+        },
+        layout => return Err(format!("Unknown layout {}", layout)),
+    };
+    let mut map = generate(layout, options.size, options.seed)?;
+    if let Some(name) = options.name {
+        map.name = name;
+    }
+    let json = serde_json::to_string_pretty(&map).map_err(|e| e.to_string())?;
+    match options.output {
+        Some(filename) => {
+            std::fs::write(&filename, json + "\n").map_err(|e| e.to_string())?;
+            eprintln!("Map {} written to {}", map.name, filename);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+///
+/// Generate a map from the command line, such as:
+/// `mapgen maze --seed 7 --output resources/maps/maze.json`
+///
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CAMPAIGN: &str = "resources/campaign.json";
+
+const PROGRESS_FILE: &str = "campaign.json";
+
+///
+/// Stage of the campaign, cleared by eating enough food on its map
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stage {
+    pub map: String, // Name of the map the stage is played on
+    pub goal: u32,   // Food to eat to clear the stage
+}
+
+///
+/// Stages of the campaign, played in order with the score carried across
+///
+/// `[{ "map": "Arena", "goal": 5 }, { "map": "Tunnels", "goal": 8 }]`
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Stage>", into = "Vec<Stage>")]
+pub struct Campaign {
+    stages: Vec<Stage>,
+}
+
+impl TryFrom<Vec<Stage>> for Campaign {
+    type Error = String;
+
+    fn try_from(stages: Vec<Stage>) -> Result<Self, Self::Error> {
+        if stages.is_empty() {
+            return Err(String::from("The campaign has no stage"));
+        }
+        if let Some(stage) = stages.iter().find(|stage| stage.goal == 0) {
+            return Err(format!("Stage on {} has no goal", stage.map));
+        }
+        Ok(Campaign { stages })
+    }
+}
+
+impl From<Campaign> for Vec<Stage> {
+    fn from(campaign: Campaign) -> Self {
+        campaign.stages
+    }
+}
+
+impl Campaign {
+    pub fn load(filename: &Path) -> Result<Campaign, String> {
+        let json = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", filename.display(), e))
+    }
+
+    /// Campaign file of the game, no stage if it can't be loaded
+    pub fn load_default() -> Campaign {
+        Campaign::load(Path::new(DEFAULT_CAMPAIGN)).unwrap_or_else(|e| {
+            println!("Campaign: Failed to load ({})", e);
+            Campaign::default()
+        })
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    pub fn stage(&self, index: usize) -> Option<&Stage> {
+        self.stages.get(index)
+    }
+}
+
+///
+/// Stages the player can start the campaign from
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub unlocked: usize, // Number of stages unlocked, the first one always is
+}
+
+impl Default for Progress {
+    fn default() -> Progress {
+        Progress { unlocked: 1 }
+    }
+}
+
+impl Progress {
+    pub fn is_unlocked(&self, stage: usize) -> bool {
+        stage < self.unlocked
+    }
+
+    /// Unlock a stage and the ones before it, returns false if it already was
+    pub fn unlock(&mut self, stage: usize) -> bool {
+        if self.is_unlocked(stage) {
+            return false;
+        }
+        self.unlocked = stage + 1;
+        true
+    }
+}
+
+pub fn save_progress(progress: &Progress) -> Result<(), String> {
+    println!("Saving campaign progress to {}", PROGRESS_FILE);
+    let json = serde_json::to_string_pretty(progress).map_err(|e| e.to_string())?;
+    std::fs::write(PROGRESS_FILE, json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+///
+/// Load the campaign progress, only the first stage if there is none yet
+///
+pub fn load_progress() -> Progress {
+    let json = match std::fs::read_to_string(PROGRESS_FILE) {
+        Ok(json) => json,
+        Err(_) => return Progress::default(),
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        println!("Invalid campaign progress file ({}), starting over", e);
+        Progress::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::map::Map;
+
+    use super::*;
+
+    #[test]
+    fn test_default_campaign() {
+        let campaign = Campaign::load(Path::new(DEFAULT_CAMPAIGN)).unwrap();
+        assert!(!campaign.stages().is_empty());
+        for stage in campaign.stages() {
+            assert!(Map::find(&stage.map).is_some(), "{}", stage.map);
+        }
+    }
+
+    #[test]
+    fn test_validation() {
+        let campaign = |json: &str| serde_json::from_str::<Campaign>(json);
+        assert!(campaign(r#"[{"map": "Arena", "goal": 5}]"#).is_ok());
+        assert!(campaign("[]").is_err());
+        assert!(campaign(r#"[{"map": "Arena", "goal": 0}]"#).is_err());
+    }
+
+    #[test]
+    fn test_unlock() {
+        let mut progress = Progress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+        assert!(progress.unlock(2));
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.unlock(1));
+        assert_eq!(progress.unlocked, 3);
+    }
+}
@@ -0,0 +1,250 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::BorderMode;
+use crate::generator::{generate, Layout};
+use crate::map::Map;
+use crate::mode::GameMode;
+
+const DAILY_FILE: &str = "daily.json";
+
+/// Grid of the daily challenge maps
+pub const DAILY_GRID_SIZE: (u32, u32) = (40, 30);
+
+/// Number of days shown in the daily results
+pub const MAX_DAILY_RESULTS: usize = 10;
+
+/// Days since the Unix epoch, in UTC
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+///
+/// Calendar date of a day since the Unix epoch, such as 2026-10-18
+///
+pub fn date(day: u64) -> String {
+    // Days since 0000-03-01, years starting in March put the leap day at their end
+    let days = day + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{}-{:02}-{:02}", year, month, day_of_month)
+}
+
+///
+/// Challenge of a day, everybody playing on that day gets the same one
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub day: u64,                // Days since the Unix epoch
+    pub seed: u64,               // Seed of the map, the food and the power-ups
+    pub layout: Layout,          // Kind of map generated from the seed
+    pub mode: GameMode,          // Rules of the challenge
+    pub border_mode: BorderMode, // Edge of the grid
+}
+
+impl Challenge {
+    pub fn for_day(day: u64) -> Challenge {
+        // Mix the day, so the seeds of two days in a row are far apart
+        let mut seed = day.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        seed ^= seed >> 31;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let layout = match rng.random_range(0..3) {
+            0 => Layout::Maze,
+            1 => Layout::Obstacles {
+                density: rng.random_range(5..=20),
+            },
+            _ => Layout::Symmetric {
+                density: rng.random_range(10..=30),
+            },
+        };
+        let modes = [GameMode::Classic, GameMode::TimeAttack, GameMode::Shrinking];
+        let mode = modes[rng.random_range(0..modes.len())];
+        let border_mode = if rng.random_bool(0.5) {
+            BorderMode::Wrap
+        } else {
+            BorderMode::Walls
+        };
+        Challenge {
+            day,
+            seed,
+            layout,
+            mode,
+            border_mode,
+        }
+    }
+
+    /// Map of the challenge, generated from its seed
+    pub fn map(&self) -> Result<Map, String> {
+        let mut map = generate(self.layout, DAILY_GRID_SIZE, self.seed)?;
+        map.name = format!("Daily {}", date(self.day));
+        Ok(map)
+    }
+
+    /// Short text of the rules, such as "Maze, Time attack, walls"
+    pub fn description(&self) -> String {
+        let border = match self.border_mode {
+            BorderMode::Wrap => "wrap around",
+            BorderMode::Walls => "walls",
+        };
+        format!("{}, {}, {}", self.layout.name(), self.mode.label(), border)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub day: u64,       // Day of the challenge
+    pub score: u32,     // Score at the end of the game
+    pub level: u32,     // Level reached
+    pub time_ms: u64,   // Game time, in ms
+    pub finished: bool, // Whether the game ended, the attempt counts from its start
+}
+
+///
+/// Attempts at the daily challenges, newest first
+/// Kept apart from the high scores, the boards change every day
+///
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResults {
+    pub results: Vec<DailyResult>,
+}
+
+impl DailyResults {
+    pub fn get(&self, day: u64) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.day == day)
+    }
+
+    ///
+    /// Record the start of the attempt of a day
+    /// Returns false if the day already has one, there is one attempt per day
+    ///
+    pub fn start(&mut self, day: u64) -> bool {
+        if self.get(day).is_some() {
+            return false;
+        }
+        self.results.insert(
+            0,
+            DailyResult {
+                day,
+                score: 0,
+                level: 0,
+                time_ms: 0,
+                finished: false,
+            },
+        );
+        self.results.truncate(MAX_DAILY_RESULTS);
+        true
+    }
+
+    ///
+    /// Record the end of the attempt of a day
+    /// Returns false if the attempt already ended, or never started
+    ///
+    pub fn finish(&mut self, day: u64, score: u32, level: u32, time_ms: u64) -> bool {
+        match self.results.iter_mut().find(|result| result.day == day) {
+            Some(result) if !result.finished => {
+                *result = DailyResult {
+                    day,
+                    score,
+                    level,
+                    time_ms,
+                    finished: true,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn save_daily_results(results: &DailyResults) -> Result<(), String> {
+    println!("Saving daily results to {}", DAILY_FILE);
+    let json = serde_json::to_string_pretty(results).map_err(|e| e.to_string())?;
+    std::fs::write(DAILY_FILE, json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+///
+/// Load the daily results, none if no challenge was played yet
+///
+pub fn load_daily_results() -> DailyResults {
+    let json = match std::fs::read_to_string(DAILY_FILE) {
+        Ok(json) => json,
+        Err(_) => return DailyResults::default(),
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        println!("Invalid daily results file ({}), starting over", e);
+        DailyResults::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::entity::Block;
+    use crate::generator::all_reachable;
+
+    use super::*;
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(11_017), "2000-03-01");
+        assert_eq!(date(20_744), "2026-10-18");
+    }
+
+    #[test]
+    fn test_same_day() {
+        assert_eq!(Challenge::for_day(20_744), Challenge::for_day(20_744));
+        assert_ne!(
+            Challenge::for_day(20_744).seed,
+            Challenge::for_day(20_745).seed
+        );
+        let map = Challenge::for_day(20_744).map().unwrap();
+        assert_eq!(map, Challenge::for_day(20_744).map().unwrap());
+        assert_eq!(map.name, "Daily 2026-10-18");
+    }
+
+    #[test]
+    fn test_playable() {
+        for day in 20_700..20_760 {
+            let challenge = Challenge::for_day(day);
+            let map = challenge.map().unwrap();
+            let start = Block(DAILY_GRID_SIZE.0 / 2, DAILY_GRID_SIZE.1 / 2);
+            assert!(all_reachable(&map, start), "{}", challenge.description());
+            assert_ne!(challenge.mode, GameMode::Zen);
+        }
+    }
+
+    #[test]
+    fn test_one_attempt_per_day() {
+        let mut results = DailyResults::default();
+        assert!(!results.finish(1, 10, 1, 0));
+        assert!(results.start(1));
+        assert!(!results.start(1));
+        assert!(results.finish(1, 10, 1, 5000));
+        assert!(!results.finish(1, 20, 2, 9000));
+        assert_eq!(results.get(1).unwrap().score, 10);
+        for day in 2..20 {
+            results.start(day);
+        }
+        assert_eq!(results.results.len(), MAX_DAILY_RESULTS);
+        assert_eq!(results.results[0].day, 19);
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+/// Ticks to eat the next food and keep the combo going
+pub const COMBO_WINDOW: u32 = 25;
+
+/// Highest score multiplier of a combo
+pub const MAX_COMBO: u32 = 5;
+
+///
+/// Score multiplier going up when food is eaten in quick succession
+/// When the window runs out, the multiplier goes down by one step at a time
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Combo {
+    pub multiplier: u32, // Score multiplier of the next food
+    pub ticks_left: u32, // Ticks before the multiplier goes down, no combo running if 0
+    pub best: u32,       // Highest multiplier of the game
+}
+
+impl Default for Combo {
+    fn default() -> Combo {
+        Combo {
+            multiplier: 1,
+            ticks_left: 0,
+            best: 1,
+        }
+    }
+}
+
+impl Combo {
+    ///
+    /// Food eaten, returns the multiplier of its score
+    ///
+    pub fn eat(&mut self) -> u32 {
+        if self.ticks_left > 0 {
+            self.multiplier = (self.multiplier + 1).min(MAX_COMBO);
+        }
+        self.ticks_left = COMBO_WINDOW;
+        self.best = self.best.max(self.multiplier);
+        self.multiplier
+    }
+
+    /// Count down the window, the multiplier decays when it runs out
+    pub fn tick(&mut self) {
+        if self.ticks_left == 0 {
+            return;
+        }
+        self.ticks_left -= 1;
+        if self.ticks_left == 0 && self.multiplier > 1 {
+            self.multiplier -= 1;
+            if self.multiplier > 1 {
+                self.ticks_left = COMBO_WINDOW;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_combo() {
+        let mut combo = Combo::default();
+        assert_eq!(combo.eat(), 1);
+        combo.tick();
+        assert_eq!(combo.eat(), 2);
+        assert_eq!(combo.eat(), 3);
+        for _ in 0..MAX_COMBO {
+            combo.eat();
+        }
+        assert_eq!(combo.multiplier, MAX_COMBO);
+
+        // Down by one step every window
+        for _ in 0..COMBO_WINDOW {
+            combo.tick();
+        }
+        assert_eq!(combo.multiplier, MAX_COMBO - 1);
+        for _ in 0..COMBO_WINDOW * MAX_COMBO {
+            combo.tick();
+        }
+        assert_eq!(combo.multiplier, 1);
+        assert_eq!(combo.ticks_left, 0);
+        assert_eq!(combo.eat(), 1);
+        assert_eq!(combo.best, MAX_COMBO);
+    }
+
+    #[test]
+    fn test_combo_decay_keeps_window() {
+        let mut combo = Combo::default();
+        combo.eat();
+        combo.eat();
+        combo.eat();
+        for _ in 0..COMBO_WINDOW {
+            combo.tick();
+        }
+        // Still in a combo, the next food goes back up
+        assert_eq!(combo.multiplier, 2);
+        assert_eq!(combo.eat(), 3);
+    }
+}
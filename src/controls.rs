@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+use snake::gamepad::Gamepads;
+use snake::input::{Action, KeyBindings};
+use snake::settings::{load_settings, save_settings};
+use snake::theme::Theme;
+
+use crate::ui::Column;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ControlsChoice {
+    Rebind(Action),
+    Reset,
+    Back,
+}
+
+///
+/// One row per action with its keys, the message is the last widget
+///
+fn rows(bindings: &KeyBindings, waiting: Option<Action>, message: &str) -> Column<ControlsChoice> {
+    let mut ui = Column::new(100, 20, 500, 36);
+    for action in Action::all() {
+        let keys: Vec<String> = bindings.keys(action).iter().map(|k| k.name()).collect();
+        let text = if waiting == Some(action) {
+            format!("{:<12}...", action.label())
+        } else {
+            format!("{:<12}{}", action.label(), keys.join(", "))
+        };
+        ui = ui.button(&text, ControlsChoice::Rebind(action));
+    }
+    ui.button("Reset defaults", ControlsChoice::Reset)
+        .button("Back", ControlsChoice::Back)
+        .label(message)
+}
+
+///
+/// Key bindings screen
+///
+/// Pick an action then press the new key, Escape cancels.
+/// A key already bound to another action is refused.
+///
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), String> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let mut settings = load_settings();
+    let theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone).ok();
+
+    let mut waiting: Option<Action> = None; // Action waiting for its new key
+    let mut message = String::from("Pick an action to change its key");
+    let mut ui = rows(&settings.key_bindings, waiting, &message);
+
+    loop {
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
+
+        for event in event_pump.poll_iter() {
+            let action = match event {
+                Event::Quit { .. } => return Ok(()),
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => {
+                    if let Some(action) = waiting.take() {
+                        message = if key == Keycode::Escape {
+                            String::from("Cancelled")
+                        } else {
+                            match settings.key_bindings.bind(action, key) {
+                                Ok(()) => {
+                                    save_settings(&settings)?;
+                                    format!("{} bound to {}", action.label(), key.name())
+                                }
+                                Err(other) => {
+                                    format!("{} is already used by {}", key.name(), other.label())
+                                }
+                            }
+                        };
+                        let focus = ui.focus();
+                        ui = rows(&settings.key_bindings, waiting, &message);
+                        ui.set_focus(focus);
+                        continue;
+                    }
+                    settings.key_bindings.action(key)
+                }
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if action == Some(Action::Quit) {
+                return Ok(());
+            }
+            match ui.handle_event(&event, action) {
+                Some(ControlsChoice::Rebind(action)) => {
+                    waiting = Some(action);
+                    message = format!("Press a key for {} (Escape to cancel)", action.label());
+                }
+                Some(ControlsChoice::Reset) => {
+                    settings.key_bindings = KeyBindings::default();
+                    save_settings(&settings)?;
+                    message = String::from("Default keys restored");
+                }
+                Some(ControlsChoice::Back) => return Ok(()),
+                None => continue,
+            }
+            let focus = ui.focus();
+            ui = rows(&settings.key_bindings, waiting, &message);
+            ui.set_focus(focus);
+        }
+
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
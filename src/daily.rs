@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+use snake::challenge::{date, load_daily_results, today, Challenge, DailyResults};
+use snake::gamepad::Gamepads;
+use snake::input::Action;
+use snake::mode::format_time;
+use snake::settings::load_settings;
+use snake::theme::Theme;
+
+use crate::ui::Column;
+
+#[derive(Debug, Clone, PartialEq)]
+enum DailyChoice {
+    Play,
+    Back,
+}
+
+///
+/// Challenge of the day and the results of the last days
+/// There is one attempt per day, the play button is disabled once it started
+///
+fn widgets(challenge: &Challenge, results: &DailyResults) -> Column<DailyChoice> {
+    let played = results.get(challenge.day).is_some();
+    let mut ui = Column::new(100, 20, 600, 36)
+        .label(&format!("Daily challenge {}", date(challenge.day)))
+        .label(&challenge.description())
+        .button(
+            if played { "Played today" } else { "Play" },
+            DailyChoice::Play,
+        )
+        .enabled(!played)
+        .label("Last results");
+    for result in &results.results {
+        let text = if result.finished {
+            format!(
+                "{}  {:>6}  Level {:<3} {}",
+                date(result.day),
+                result.score,
+                result.level,
+                format_time(result.time_ms)
+            )
+        } else {
+            format!("{}  Not finished", date(result.day))
+        };
+        ui = ui.label(&text);
+    }
+    if results.results.is_empty() {
+        ui = ui.label("No challenge played yet");
+    }
+    ui.button("Back", DailyChoice::Back)
+}
+
+///
+/// Daily challenge screen, returns true to play the challenge of the day
+///
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<bool, String> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let settings = load_settings();
+    let theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone).ok();
+
+    let mut ui = widgets(&Challenge::for_day(today()), &load_daily_results());
+
+    loop {
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
+
+        for event in event_pump.poll_iter() {
+            let action = match event {
+                Event::Quit { .. } => return Ok(false),
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => settings.key_bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if action == Some(Action::Quit) {
+                return Ok(false);
+            }
+            match ui.handle_event(&event, action) {
+                Some(DailyChoice::Play) => return Ok(true),
+                Some(DailyChoice::Back) => return Ok(false),
+                None => {}
+            }
+        }
+
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entity::BorderMode;
+use crate::settings::Settings;
+
+///
+/// How the time between two ticks goes down with the level
+/// The time never goes below the maximum speed of the difficulty
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SpeedCurve {
+    #[default]
+    Linear, // Faster by the speed increase every level
+    Stepwise {
+        levels: u32, // Faster by the increase of all those levels at once
+    },
+    Exponential {
+        percent: u32, // Shorter ticks by this percent every level
+    },
+    Capped {
+        level: u32, // Linear up to this level, then the speed stays the same
+    },
+}
+
+impl SpeedCurve {
+    /// Curves offered for a custom difficulty
+    pub fn all() -> [SpeedCurve; 4] {
+        [
+            SpeedCurve::Linear,
+            SpeedCurve::Stepwise { levels: 3 },
+            SpeedCurve::Exponential { percent: 10 },
+            SpeedCurve::Capped { level: 5 },
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpeedCurve::Linear => "Linear",
+            SpeedCurve::Stepwise { .. } => "Stepwise",
+            SpeedCurve::Exponential { .. } => "Exponential",
+            SpeedCurve::Capped { .. } => "Capped",
+        }
+    }
+
+    ///
+    /// Time between two ticks at a level, in ms
+    ///
+    pub fn speed(&self, level: u32, initial: u32, increase: u32, maximum: u32) -> u32 {
+        let speed = match *self {
+            SpeedCurve::Linear => initial as f32 - (level * increase) as f32,
+            SpeedCurve::Stepwise { levels } => {
+                let levels = levels.max(1);
+                initial as f32 - (level / levels * levels * increase) as f32
+            }
+            SpeedCurve::Exponential { percent } => {
+                initial as f32 * (1.0 - percent.min(100) as f32 / 100.0).powi(level as i32)
+            }
+            SpeedCurve::Capped { level: cap } => {
+                initial as f32 - (level.min(cap) * increase) as f32
+            }
+        };
+        if speed < maximum as f32 {
+            maximum
+        } else {
+            speed.round() as u32
+        }
+    }
+}
+
+///
+/// Speed progression, food mix and border bundled under a name
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub name: String,                     // Shown in the settings and the high scores
+    pub initial_speed: u32,               // Time between two ticks at the start, in ms
+    pub speed_increase_per_level: u32,    // Used by the speed curve
+    pub maximum_speed: u32,               // Shortest time between two ticks, in ms
+    pub speed_curve: SpeedCurve,          // How the speed goes up with the level
+    pub food_weights: Vec<(String, u32)>, // Weight of food types, replacing the default
+    pub border_mode: BorderMode,          // Wrap around or walls at the edge
+}
+
+pub const CUSTOM: &str = "Custom";
+
+impl Default for Difficulty {
+    fn default() -> Difficulty {
+        Difficulty {
+            name: String::from("Normal"),
+            initial_speed: 100,
+            speed_increase_per_level: 10,
+            maximum_speed: 30,
+            speed_curve: SpeedCurve::Linear,
+            food_weights: Vec::new(),
+            border_mode: BorderMode::Wrap,
+        }
+    }
+}
+
+/// Weights of the built-in food types
+fn weights(cherry: u32, banana: u32, apple: u32) -> Vec<(String, u32)> {
+    vec![
+        (String::from("Cherry"), cherry),
+        (String::from("Banana"), banana),
+        (String::from("Apple"), apple),
+    ]
+}
+
+impl Difficulty {
+    pub fn presets() -> Vec<Difficulty> {
+        vec![
+            Difficulty {
+                name: String::from("Easy"),
+                initial_speed: 150,
+                maximum_speed: 60,
+                speed_curve: SpeedCurve::Capped { level: 6 },
+                food_weights: weights(50, 35, 15),
+                ..Difficulty::default()
+            },
+            Difficulty::default(),
+            Difficulty {
+                name: String::from("Hard"),
+                initial_speed: 80,
+                maximum_speed: 25,
+                speed_curve: SpeedCurve::Exponential { percent: 8 },
+                food_weights: weights(70, 25, 5),
+                border_mode: BorderMode::Walls,
+                ..Difficulty::default()
+            },
+            Difficulty {
+                name: String::from("Insane"),
+                initial_speed: 50,
+                speed_increase_per_level: 5,
+                maximum_speed: 20,
+                speed_curve: SpeedCurve::Stepwise { levels: 2 },
+                food_weights: weights(80, 15, 5),
+                border_mode: BorderMode::Walls,
+            },
+        ]
+    }
+
+    ///
+    /// Custom difficulty, its speed, curve and border come from the settings
+    ///
+    pub fn custom(settings: &Settings) -> Difficulty {
+        Difficulty {
+            name: String::from(CUSTOM),
+            initial_speed: settings.initial_speed,
+            speed_curve: settings.speed_curve,
+            border_mode: settings.border_mode,
+            ..Difficulty::default()
+        }
+    }
+
+    /// Find a preset by name, or the custom difficulty, Normal if it doesn't exist
+    pub fn find(name: &str, settings: &Settings) -> Difficulty {
+        if name == CUSTOM {
+            return Difficulty::custom(settings);
+        }
+        Difficulty::presets()
+            .into_iter()
+            .find(|difficulty| difficulty.name == name)
+            .unwrap_or_default()
+    }
+
+    /// Time between two ticks at a level, in ms
+    pub fn speed(&self, level: u32) -> u32 {
+        self.speed_curve.speed(
+            level,
+            self.initial_speed,
+            self.speed_increase_per_level,
+            self.maximum_speed,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_speed_curves() {
+        let speeds = |curve: SpeedCurve| -> Vec<u32> {
+            (0..6)
+                .map(|level| curve.speed(level, 100, 10, 55))
+                .collect()
+        };
+        assert_eq!(speeds(SpeedCurve::Linear), vec![100, 90, 80, 70, 60, 55]);
+        assert_eq!(
+            speeds(SpeedCurve::Stepwise { levels: 2 }),
+            vec![100, 100, 80, 80, 60, 60]
+        );
+        assert_eq!(
+            speeds(SpeedCurve::Exponential { percent: 10 }),
+            vec![100, 90, 81, 73, 66, 59]
+        );
+        assert_eq!(
+            speeds(SpeedCurve::Capped { level: 2 }),
+            vec![100, 90, 80, 80, 80, 80]
+        );
+        // Never faster than the maximum speed
+        assert_eq!(SpeedCurve::Linear.speed(100, 100, 10, 55), 55);
+    }
+
+    #[test]
+    fn test_find() {
+        let settings = Settings {
+            initial_speed: 70,
+            border_mode: BorderMode::Walls,
+            ..Settings::default()
+        };
+        assert_eq!(Difficulty::find("Hard", &settings).initial_speed, 80);
+        assert_eq!(Difficulty::find("Nope", &settings), Difficulty::default());
+        let custom = Difficulty::find(CUSTOM, &settings);
+        assert_eq!(custom.initial_speed, 70);
+        assert_eq!(custom.border_mode, BorderMode::Walls);
+        // Weights of 0 would be ignored
+        assert!(Difficulty::presets()
+            .iter()
+            .all(|d| d.food_weights.iter().all(|(_, w)| *w > 0)));
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// What eating a food does, besides its score and growth
+///
+/// In the food types file: `"effects": [{ "Speed": { "percent": 60, "ticks": 50 } }]`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect {
+    Shrink(u32), // Blocks removed from the tail, at once
+    Speed {
+        percent: u32, // Tick length in percent of the normal one, below 100 is faster
+        ticks: u32,
+    },
+    ScoreMultiplier {
+        factor: u32, // Score of the food eaten meanwhile is multiplied by this
+        ticks: u32,
+    },
+}
+
+impl Effect {
+    /// Ticks the effect lasts, None if it applies at once
+    pub fn ticks(&self) -> Option<u32> {
+        match self {
+            Effect::Shrink(_) => None,
+            Effect::Speed { ticks, .. } | Effect::ScoreMultiplier { ticks, .. } => Some(*ticks),
+        }
+    }
+
+    /// Effects of the same kind replace each other instead of stacking
+    fn same_kind(&self, other: &Effect) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub effect: Effect,
+    pub ticks_left: u32,
+}
+
+impl ActiveEffect {
+    /// Short text for the HUD, with the ticks left
+    pub fn label(&self) -> String {
+        let name = match self.effect {
+            Effect::Speed { percent, .. } if percent < 100 => String::from("Fast"),
+            Effect::Speed { .. } => String::from("Slow"),
+            Effect::ScoreMultiplier { factor, .. } => format!("x{}", factor),
+            Effect::Shrink(_) => String::from("Shrink"),
+        };
+        format!("{} {}", name, self.ticks_left)
+    }
+}
+
+///
+/// Timed effects running in a game
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffects {
+    effects: Vec<ActiveEffect>,
+}
+
+impl ActiveEffects {
+    /// Start a timed effect, instant effects are ignored
+    pub fn add(&mut self, effect: Effect) {
+        let Some(ticks) = effect.ticks() else {
+            return;
+        };
+        self.effects
+            .retain(|active| !active.effect.same_kind(&effect));
+        self.effects.push(ActiveEffect {
+            effect,
+            ticks_left: ticks,
+        });
+    }
+
+    /// Count down every effect, and remove the ones that ended
+    pub fn tick(&mut self) {
+        for active in &mut self.effects {
+            active.ticks_left = active.ticks_left.saturating_sub(1);
+        }
+        self.effects.retain(|active| active.ticks_left > 0);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveEffect> {
+        self.effects.iter()
+    }
+
+    /// Tick length in percent of the normal one
+    pub fn speed_percent(&self) -> u32 {
+        self.effects
+            .iter()
+            .find_map(|active| match active.effect {
+                Effect::Speed { percent, .. } => Some(percent),
+                _ => None,
+            })
+            .unwrap_or(100)
+    }
+
+    pub fn score_multiplier(&self) -> u32 {
+        self.effects
+            .iter()
+            .find_map(|active| match active.effect {
+                Effect::ScoreMultiplier { factor, .. } => Some(factor),
+                _ => None,
+            })
+            .unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_effects() {
+        let mut effects = ActiveEffects::default();
+        assert_eq!(effects.speed_percent(), 100);
+        assert_eq!(effects.score_multiplier(), 1);
+
+        effects.add(Effect::Shrink(3));
+        effects.add(Effect::Speed {
+            percent: 50,
+            ticks: 2,
+        });
+        effects.add(Effect::ScoreMultiplier {
+            factor: 3,
+            ticks: 5,
+        });
+        assert_eq!(effects.iter().count(), 2);
+        assert_eq!(effects.speed_percent(), 50);
+        assert_eq!(effects.score_multiplier(), 3);
+
+        // The slow motion replaces the speed boost
+        effects.add(Effect::Speed {
+            percent: 200,
+            ticks: 3,
+        });
+        assert_eq!(effects.iter().count(), 2);
+        assert_eq!(effects.speed_percent(), 200);
+        let labels: Vec<String> = effects.iter().map(|a| a.label()).collect();
+        assert_eq!(labels, vec!["x3 5", "Slow 3"]);
+
+        effects.tick();
+        effects.tick();
+        effects.tick();
+        assert_eq!(effects.speed_percent(), 100);
+        assert_eq!(effects.score_multiplier(), 3);
+        effects.tick();
+        effects.tick();
+        assert_eq!(effects.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_effects_file() {
+        let effects: Vec<Effect> =
+            serde_json::from_str(r#"[{"Shrink": 3}, {"Speed": {"percent": 60, "ticks": 50}}]"#)
+                .unwrap();
+        assert_eq!(
+            effects,
+            vec![
+                Effect::Shrink(3),
+                Effect::Speed {
+                    percent: 60,
+                    ticks: 50
+                }
+            ]
+        );
+    }
+}
@@ -1,55 +1,172 @@
 use std::sync::Arc;
 
-use sdl2::pixels::Color;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+use crate::campaign::Campaign;
+use crate::challenge::Challenge;
+use crate::combo::Combo;
+use crate::difficulty::{Difficulty, SpeedCurve};
+use crate::effect::ActiveEffects;
+use crate::food::FoodTypes;
+use crate::hazard::Hazard;
+use crate::map::Map;
+use crate::mode::{open_area, GameMode};
+use crate::powerup::{ActivePowerUps, PowerUp};
+use crate::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
-
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct Block(pub u32, pub u32);
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum FoodType {
-    Cherry,
-    Banana,
-    Apple,
-}
-impl FoodType {
-    pub fn texture(&self) -> String {
+impl Direction {
+    /// Direction after a quarter turn counterclockwise
+    pub fn turn_left(&self) -> Direction {
         match self {
-            FoodType::Cherry => "resources/cherry.png".to_string(),
-            FoodType::Banana => "resources/banana.png".to_string(),
-            FoodType::Apple => "resources/apple.png".to_string(),
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
         }
     }
-    pub fn score(&self) -> u32 {
+    /// Direction after a quarter turn clockwise
+    pub fn turn_right(&self) -> Direction {
+        self.turn_left().opposite()
+    }
+    pub fn opposite(&self) -> Direction {
         match self {
-            FoodType::Cherry => 1,
-            FoodType::Banana => 3,
-            FoodType::Apple => 5,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
         }
     }
-    pub fn increase(&self) -> u32 {
+}
+
+///
+/// How the keys steer the snake
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    #[default]
+    Absolute, // One key per direction
+    Relative, // Two keys, turn left or right from the current heading
+}
+impl ControlScheme {
+    pub fn all() -> [ControlScheme; 2] {
+        [ControlScheme::Absolute, ControlScheme::Relative]
+    }
+
+    pub fn label(&self) -> &'static str {
         match self {
-            FoodType::Cherry => 2,
-            FoodType::Banana => 5,
-            FoodType::Apple => 8,
+            ControlScheme::Absolute => "Absolute",
+            ControlScheme::Relative => "Relative",
         }
     }
-    pub fn probality(&self) -> u32 {
-        match self {
-            FoodType::Cherry => 60,
-            FoodType::Banana => 30,
-            FoodType::Apple => 10,
+}
+
+///
+/// What happens when the snake reaches the edge of the grid
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BorderMode {
+    #[default]
+    Wrap, // Come back from the opposite edge
+    Walls, // The edge kills the snake
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Block(pub u32, pub u32);
+
+impl Block {
+    ///
+    /// Position between two neighbouring blocks, in cells
+    /// alpha goes from 0.0 (on `from`) to 1.0 (on `to`)
+    ///
+    /// When the move wraps around the grid the position goes past the edge
+    /// (below 0 or above the grid size) instead of crossing the whole screen.
+    ///
+    pub fn lerp(from: &Block, to: &Block, alpha: f32, grid_size: (u32, u32)) -> (f32, f32) {
+        let axis = |from: u32, to: u32, size: u32| -> f32 {
+            let mut delta = to as f32 - from as f32;
+            if delta > 1.0 {
+                delta -= size as f32;
+            } else if delta < -1.0 {
+                delta += size as f32;
+            }
+            from as f32 + delta * alpha
+        };
+        (
+            axis(from.0, to.0, grid_size.0),
+            axis(from.1, to.1, grid_size.1),
+        )
+    }
+
+    ///
+    /// Direction of a neighbouring block, including across the edges of the grid
+    /// Returns None if the blocks are not neighbours
+    ///
+    pub fn direction_to(&self, other: &Block, grid_size: (u32, u32)) -> Option<Direction> {
+        let (width, height) = grid_size;
+        if self.1 == other.1 {
+            if (self.0 + 1) % width == other.0 {
+                return Some(Direction::Right);
+            }
+            if (other.0 + 1) % width == self.0 {
+                return Some(Direction::Left);
+            }
+        }
+        if self.0 == other.0 {
+            if (self.1 + 1) % height == other.1 {
+                return Some(Direction::Down);
+            }
+            if (other.1 + 1) % height == self.1 {
+                return Some(Direction::Up);
+            }
         }
+        None
     }
 }
+
+///
+/// Sprite used to draw a snake segment
+///
+/// The sprites are drawn facing up, the direction is the rotation to apply:
+/// - Head: direction the snake is moving to
+/// - Body: Up for a vertical segment, Right for a horizontal one
+/// - Corner: Up joins the top and right sides, every rotation turns it clockwise
+/// - Tail: direction the tail is moving to
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnakePiece {
+    Head(Direction),
+    Body(Direction),
+    Corner(Direction),
+    Tail(Direction),
+}
+impl SnakePiece {
+    /// Body or corner piece joining two sides of a cell
+    fn joining(a: Direction, b: Direction) -> SnakePiece {
+        use Direction::*;
+        match (a, b) {
+            (Up, Down) | (Down, Up) => SnakePiece::Body(Up),
+            (Left, Right) | (Right, Left) => SnakePiece::Body(Right),
+            (Up, Right) | (Right, Up) => SnakePiece::Corner(Up),
+            (Right, Down) | (Down, Right) => SnakePiece::Corner(Right),
+            (Down, Left) | (Left, Down) => SnakePiece::Corner(Down),
+            (Left, Up) | (Up, Left) => SnakePiece::Corner(Left),
+            // Both neighbours on the same side, only possible on a collision
+            (a, _) => SnakePiece::Body(match a {
+                Up | Down => Up,
+                Left | Right => Right,
+            }),
+        }
+    }
+}
+
 // #[derive(Debug)]
 // pub struct Color {
 //     pub r: u8,
@@ -81,17 +198,27 @@ impl Snake {
         }
     }
 
-    /// Update the snake position / next tick
-    pub fn update(&mut self, config: Arc<Config>) {
+    /// Position of the head after the next move, before wrapping around the grid
+    fn next_head(&self) -> (i32, i32) {
         let Block(head_x, head_y) = self.body[0];
-
-        // Create a new head based on the current direction of the snake
-        let mut new_head: (i32, i32) = match self.direction {
+        match self.direction {
             Direction::Up => (head_x as i32, head_y as i32 - 1),
             Direction::Down => (head_x as i32, head_y as i32 + 1),
             Direction::Left => (head_x as i32 - 1, head_y as i32),
             Direction::Right => (head_x as i32 + 1, head_y as i32),
-        };
+        }
+    }
+
+    /// Whether the next move goes past the edge of the grid
+    pub fn leaving_grid(&self, grid_size: (u32, u32)) -> bool {
+        let (x, y) = self.next_head();
+        x < 0 || y < 0 || x >= grid_size.0 as i32 || y >= grid_size.1 as i32
+    }
+
+    /// Update the snake position / next tick
+    pub fn update(&mut self, config: Arc<Config>) {
+        // Create a new head based on the current direction of the snake
+        let mut new_head: (i32, i32) = self.next_head();
 
         // handle the snake going out of bounds
         if new_head.0 >= config.grid_size.0 as i32 {
@@ -104,7 +231,13 @@ impl Snake {
             new_head.1 = config.grid_size.1 as i32 - 1;
         }
 
-        let head_block = Block(new_head.0 as u32, new_head.1 as u32);
+        let mut head_block = Block(new_head.0 as u32, new_head.1 as u32);
+
+        // Going into a portal comes out of the other end, in the same direction
+        if let Some(exit) = config.map.portal(&head_block) {
+            println!("Snake: Portal to {:?}", exit);
+            head_block = exit;
+        }
 
         self.body.insert(0, head_block); // Insert new head
 
@@ -117,21 +250,26 @@ impl Snake {
 
     /// Change the direction of the snake
     /// The snake can't go in the opposite direction
-    pub fn cd(&mut self, direction: Direction) {
+    /// Returns true if the direction changed
+    pub fn cd(&mut self, direction: Direction) -> bool {
+        if self.direction == direction {
+            return false;
+        }
         if self.direction == Direction::Up && direction == Direction::Down {
-            return;
+            return false;
         }
         if self.direction == Direction::Down && direction == Direction::Up {
-            return;
+            return false;
         }
         if self.direction == Direction::Left && direction == Direction::Right {
-            return;
+            return false;
         }
         if self.direction == Direction::Right && direction == Direction::Left {
-            return;
+            return false;
         }
         println!("Snake: Direction changed to {:?}", direction);
         self.direction = direction;
+        true
     }
 
     pub fn grow(&mut self, count: u32) {
@@ -139,42 +277,132 @@ impl Snake {
         self.eat += count;
     }
 
+    ///
+    /// Remove blocks from the tail, pending growth is cancelled first
+    /// The head is always kept
+    ///
+    pub fn shrink(&mut self, count: u32) {
+        println!("Snake: Shrink by {}", count);
+        let cancelled = count.min(self.eat);
+        self.eat -= cancelled;
+        let removed = ((count - cancelled) as usize).min(self.body.len() - 1);
+        self.body.truncate(self.body.len() - removed);
+    }
+
     pub fn head(&self) -> &Block {
         &self.body[0]
     }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    ///
+    /// Sprite of every segment, chosen from its neighbours
+    ///
+    pub fn pieces(&self, config: Arc<Config>) -> Vec<SnakePiece> {
+        let grid = config.grid_size;
+        let last = self.body.len() - 1;
+        self.body
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                // Direction of the segments towards the head and towards the tail
+                let front = i
+                    .checked_sub(1)
+                    .and_then(|j| block.direction_to(&self.body[j], grid));
+                let back = self
+                    .body
+                    .get(i + 1)
+                    .and_then(|next| block.direction_to(next, grid));
+                match (i, front, back) {
+                    (0, _, _) => SnakePiece::Head(self.direction),
+                    (i, Some(front), _) if i == last => SnakePiece::Tail(front),
+                    (_, Some(front), Some(back)) => SnakePiece::joining(front, back),
+                    (_, Some(front), None) => SnakePiece::joining(front, front.opposite()),
+                    (_, None, Some(back)) => SnakePiece::joining(back, back.opposite()),
+                    (_, None, None) => SnakePiece::joining(self.direction, self.direction),
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// Position of every segment between the previous tick and the current one
+    ///
+    /// Segment i moves from `previous[i]` to `body[i]`, segments added by the
+    /// last growth have no previous position and stay still. Segments going
+    /// through a portal jump to the other end.
+    ///
+    pub fn interpolate(
+        &self,
+        previous: &[Block],
+        alpha: f32,
+        config: Arc<Config>,
+    ) -> Vec<(f32, f32)> {
+        self.body
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let from = previous.get(i).unwrap_or(block);
+                if from != block && from.direction_to(block, config.grid_size).is_none() {
+                    return (block.0 as f32, block.1 as f32);
+                }
+                Block::lerp(from, block, alpha, config.grid_size)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Food {
-    pub type_: FoodType, // Food type
+    pub type_: String,   // Id of the food type
     pub position: Block, // Food position on the screen
+    #[serde(default)]
+    pub ticks_left: Option<u32>, // Ticks before the food disappears, forever if None
 }
 impl Food {
-    pub fn new(config: Arc<Config>) -> Food {
+    ///
+    /// Random food on the part of the grid still open, the middle block if it's all closed
+    ///
+    pub fn new(config: Arc<Config>, closed_rings: u32, rng: &mut impl Rng) -> Food {
         // Random food type
-        use rand::Rng;
-
-        let mut rng = rand::rng();
-        let random_value = rng.random_range(0..100);
-        let type_ = if random_value < FoodType::Cherry.probality() {
-            FoodType::Cherry
-        } else if random_value < FoodType::Cherry.probality() + FoodType::Banana.probality() {
-            FoodType::Banana
-        } else {
-            FoodType::Apple
-        };
-        // Randomize the position of the food
-        let mut rng = rand::rng();
-        let x = rng.random_range(0..config.grid_size.0);
-        let y = rng.random_range(0..config.grid_size.1);
+        let food_type = config.food_types.pick(rng);
+        // Randomize the position of the food, away from the walls and portals
+        let (columns, rows) = open_area(config.grid_size, closed_rings);
+        let mut position = Block(config.grid_size.0 / 2, config.grid_size.1 / 2);
+        if !columns.is_empty() && !rows.is_empty() {
+            for _ in 0..100 {
+                position = Block(
+                    rng.random_range(columns.clone()),
+                    rng.random_range(rows.clone()),
+                );
+                if !config.map.blocked(&position) {
+                    break;
+                }
+            }
+        }
         Food {
-            type_,
-            position: Block(x, y),
+            type_: food_type.id.clone(),
+            position,
+            ticks_left: food_type.lifetime,
+        }
+    }
+
+    /// Count down the lifetime, returns true when the food disappears
+    pub fn expire(&mut self) -> bool {
+        match self.ticks_left {
+            Some(0) => true,
+            Some(ref mut ticks) => {
+                *ticks -= 1;
+                *ticks == 0
+            }
+            None => false,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
     pub grid_size: (u32, u32),         // Grid size
     pub grid_resolution: u32,          // Grid resolution in pixels
@@ -184,12 +412,24 @@ pub struct Config {
     pub starting_position: (u32, u32), // Starting position of the snake
     pub speed_increase_per_level: u32, // Speed increase per level
     pub score_per_level: u32,          // Score per level
-    pub background_color: Color,       // Background color
-    pub snake_color: Color,            // Snake color
-    pub food_color: Color,             // Food color
+    pub smooth_movement: bool,         // Interpolate the snake between two ticks
+    pub theme: Theme,                  // Colors, font and sprites
+    pub shape_markers: bool,           // Draw shapes over food and hazards
+    pub control_scheme: ControlScheme, // Control scheme of new games
+    pub border_mode: BorderMode,       // Wrap around or walls at the edge of the grid
+    pub speed_curve: SpeedCurve,       // How the speed goes up with the level
+    pub food_types: FoodTypes,         // Food types, weighted by the difficulty
+    pub difficulty: String,            // Name of the difficulty, recorded in the high scores
+    pub player_name: String,           // Name of the player, recorded in the high scores
+    pub map: Map,                      // Walls and portals, empty without a map
+    pub mode: GameMode,                // Rules of the game
+    pub campaign: Campaign,            // Stages of the campaign, empty outside of it
+    pub stage: Option<usize>,          // Campaign stage, starting from 0
+    pub daily: Option<Challenge>,      // Daily challenge, its seed makes the game the same for all
 }
 
 impl Config {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Config {
         Config {
             grid_size: (80, 60),
@@ -200,9 +440,20 @@ impl Config {
             starting_position: (40, 30),
             speed_increase_per_level: 10,
             score_per_level: 10,
-            background_color: Color::RGB(0, 0, 0),
-            snake_color: Color::RGB(0, 255, 0),
-            food_color: Color::RGB(255, 0, 0),
+            smooth_movement: false,
+            theme: Theme::default(),
+            shape_markers: false,
+            control_scheme: ControlScheme::Absolute,
+            border_mode: BorderMode::Wrap,
+            speed_curve: SpeedCurve::Linear,
+            food_types: FoodTypes::default(),
+            difficulty: Difficulty::default().name,
+            player_name: String::new(),
+            map: Map::default(),
+            mode: GameMode::Classic,
+            campaign: Campaign::default(),
+            stage: None,
+            daily: None,
         }
     }
 }
@@ -213,21 +464,92 @@ pub struct GameState {
     pub snake: Snake,       // Snake
     pub score: u32,         // Score of the game
     pub level: u32,         // Level of the game
-    pub speed: u32,         // Speed of the game
+    pub speed: u32,         // Speed of the game (time between two ticks in ms)
     pub game_over: bool,    // Game over flag
+    #[serde(default)]
+    pub control_scheme: ControlScheme, // Control scheme the game is played with
+    #[serde(default = "default_grid_size")]
+    pub grid_size: (u32, u32), // Grid the game is played on
+    #[serde(default)]
+    pub border_mode: BorderMode, // Edge of the grid the game is played with
+    #[serde(default = "default_difficulty")]
+    pub difficulty: String, // Name of the difficulty the game is played with
+    #[serde(default)]
+    pub effects: ActiveEffects, // Timed effects of the food eaten
+    #[serde(default)]
+    pub combo: Combo, // Score multiplier of food eaten in quick succession
+    #[serde(default)]
+    pub power_up: Option<PowerUp>, // Power-up waiting to be picked up
+    #[serde(default)]
+    pub power_ups: ActivePowerUps, // Power-ups picked up
+    #[serde(default)]
+    pub map: Option<String>, // Name of the map the game is played on
+    #[serde(default)]
+    pub hazards: Vec<Hazard>, // Moving hazards of the map, where they are now
+    #[serde(default)]
+    pub mode: GameMode, // Rules the game is played with
+    #[serde(default)]
+    pub ticks: u32, // Ticks since the start of the game
+    #[serde(default)]
+    pub elapsed: u64, // Game time since the start of the game, in ms
+    #[serde(default)]
+    pub won: bool, // Whether the game ended by reaching the goal of its mode
+    #[serde(default)]
+    pub stage: Option<usize>, // Campaign stage the game is played on
+    #[serde(default)]
+    pub eaten: u32, // Food eaten since the start of the game or of the campaign stage
+    #[serde(default)]
+    pub daily: Option<u64>, // Day of the daily challenge the game is played on
+}
+
+/// Grid of the saves made before the grid size could be changed
+fn default_grid_size() -> (u32, u32) {
+    Config::default().grid_size
+}
+
+/// Difficulty of the saves made before difficulties could be chosen
+fn default_difficulty() -> String {
+    Difficulty::default().name
 }
 
 impl GameState {
     pub fn new(config: Arc<Config>) -> GameState {
         let snake = Snake::new(config.clone());
-        let speed = config.initial_speed;
+        // Each campaign stage starts one level higher, and faster
+        let level = config.stage.map_or(0, |stage| stage as u32);
+        let speed = match config.stage {
+            Some(_) => config.speed_curve.speed(
+                level,
+                config.initial_speed,
+                config.speed_increase_per_level,
+                config.maximum_speed,
+            ),
+            None => config.initial_speed,
+        };
         GameState {
             food: None,
             snake,
             score: 0,
-            level: 0,
+            level,
             speed,
             game_over: false,
+            control_scheme: config.control_scheme,
+            grid_size: config.grid_size,
+            border_mode: config.border_mode,
+            difficulty: config.difficulty.clone(),
+            effects: ActiveEffects::default(),
+            combo: Combo::default(),
+            power_up: None,
+            power_ups: ActivePowerUps::default(),
+            map: (!config.map.name.is_empty()).then(|| config.map.name.clone()),
+            hazards: config.map.hazards.clone(),
+            mode: config.mode,
+            ticks: 0,
+            elapsed: 0,
+            won: false,
+            stage: config.stage,
+            eaten: 0,
+            daily: config.daily.as_ref().map(|challenge| challenge.day),
         }
     }
 }
@@ -250,6 +572,24 @@ mod tests {
         assert_eq!(snake.head(), &Block(40, 30));
     }
 
+    #[test]
+    fn test_snake_leaving_grid() {
+        let config = Arc::new(Config {
+            grid_size: (10, 10),
+            starting_position: (1, 0),
+            ..Config::default()
+        });
+        let mut snake = Snake::new(config.clone());
+        assert!(!snake.leaving_grid(config.grid_size));
+        snake.update(config.clone());
+        assert!(snake.leaving_grid(config.grid_size));
+        snake.cd(Direction::Down);
+        assert!(!snake.leaving_grid(config.grid_size));
+        snake.cd(Direction::Right);
+        assert!(!snake.leaving_grid(config.grid_size));
+        assert!(snake.leaving_grid((1, 10)));
+    }
+
     #[test]
     fn test_snake_update() {
         let config = Arc::new(Config {
@@ -270,6 +610,35 @@ mod tests {
         assert_eq!(snake.body[1], Block(39, 30));
         assert_eq!(snake.body.len(), 2);
     }
+
+    #[test]
+    fn test_snake_portal() {
+        let config = Arc::new(Config {
+            grid_size: (10, 1),
+            starting_position: (5, 0),
+            initial_size: 2,
+            map: Map {
+                name: String::from("Test"),
+                grid_size: (10, 1),
+                walls: Vec::new(),
+                portals: vec![(Block(4, 0), Block(8, 0))],
+                hazards: Vec::new(),
+            },
+            ..Config::default()
+        });
+        let mut snake = Snake::new(config.clone());
+        let previous = snake.body.clone();
+        snake.update(config.clone());
+        // Out of the other end, still going left, the body follows
+        assert_eq!(snake.body, vec![Block(8, 0), Block(5, 0)]);
+        assert_eq!(
+            snake.interpolate(&previous, 0.5, config.clone()),
+            vec![(8.0, 0.0), (5.0, 0.0)]
+        );
+        snake.update(config.clone());
+        assert_eq!(snake.body, vec![Block(7, 0), Block(8, 0)]);
+    }
+
     #[test]
     fn test_snake_cd() {
         let config = Arc::new(Config {
@@ -347,12 +716,146 @@ mod tests {
         assert_eq!(snake.body[0], Block(9, 9));
     }
     #[test]
+    fn test_block_lerp() {
+        let grid = (10, 10);
+        assert_eq!(
+            Block::lerp(&Block(4, 5), &Block(5, 5), 0.0, grid),
+            (4.0, 5.0)
+        );
+        assert_eq!(
+            Block::lerp(&Block(4, 5), &Block(5, 5), 0.5, grid),
+            (4.5, 5.0)
+        );
+        assert_eq!(
+            Block::lerp(&Block(4, 5), &Block(4, 4), 0.25, grid),
+            (4.0, 4.75)
+        );
+        // Wrap around the edges
+        assert_eq!(
+            Block::lerp(&Block(0, 5), &Block(9, 5), 0.5, grid),
+            (-0.5, 5.0)
+        );
+        assert_eq!(
+            Block::lerp(&Block(9, 5), &Block(0, 5), 0.5, grid),
+            (9.5, 5.0)
+        );
+        assert_eq!(
+            Block::lerp(&Block(3, 0), &Block(3, 9), 0.5, grid),
+            (3.0, -0.5)
+        );
+        assert_eq!(
+            Block::lerp(&Block(3, 9), &Block(3, 0), 0.5, grid),
+            (3.0, 9.5)
+        );
+    }
+
+    #[test]
+    fn test_snake_interpolate() {
+        let config = Arc::new(Config {
+            grid_size: (10, 10),
+            starting_position: (1, 5),
+            initial_size: 3,
+            ..Config::default()
+        });
+        let mut snake = Snake::new(config.clone());
+        snake.update(config.clone());
+        let previous = snake.body.clone();
+        snake.update(config.clone());
+        // The last segment was just added and stays still
+        assert_eq!(
+            snake.interpolate(&previous, 0.5, config.clone()),
+            vec![(-0.5, 5.0), (0.5, 5.0), (1.0, 5.0)]
+        );
+        let previous = snake.body.clone();
+        snake.update(config.clone());
+        assert_eq!(
+            snake.interpolate(&previous, 0.0, config.clone()),
+            vec![(9.0, 5.0), (0.0, 5.0), (1.0, 5.0)]
+        );
+    }
+
+    #[test]
+    fn test_direction_turn() {
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Left.turn_left(), Direction::Down);
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Right.turn_right(), Direction::Down);
+        assert_eq!(Direction::Down.turn_right(), Direction::Left);
+        assert_eq!(Direction::Left.turn_right(), Direction::Up);
+    }
+
+    #[test]
+    fn test_block_direction_to() {
+        let grid = (10, 10);
+        assert_eq!(
+            Block(4, 4).direction_to(&Block(4, 3), grid),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            Block(4, 4).direction_to(&Block(5, 4), grid),
+            Some(Direction::Right)
+        );
+        assert_eq!(
+            Block(0, 4).direction_to(&Block(9, 4), grid),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            Block(4, 9).direction_to(&Block(4, 0), grid),
+            Some(Direction::Down)
+        );
+        assert_eq!(Block(4, 4).direction_to(&Block(5, 5), grid), None);
+        assert_eq!(Block(4, 4).direction_to(&Block(4, 4), grid), None);
+    }
+
+    #[test]
+    fn test_snake_pieces() {
+        let config = Arc::new(Config {
+            grid_size: (10, 10),
+            starting_position: (1, 5),
+            initial_size: 5,
+            ..Config::default()
+        });
+        let mut snake = Snake::new(config.clone());
+        assert_eq!(
+            snake.pieces(config.clone()),
+            vec![SnakePiece::Head(Direction::Left)]
+        );
+        // Left across the edge, then up
+        snake.update(config.clone());
+        snake.update(config.clone());
+        snake.cd(Direction::Up);
+        snake.update(config.clone());
+        snake.update(config.clone());
+        assert_eq!(
+            snake.body,
+            vec![
+                Block(9, 3),
+                Block(9, 4),
+                Block(9, 5),
+                Block(0, 5),
+                Block(1, 5)
+            ]
+        );
+        assert_eq!(
+            snake.pieces(config.clone()),
+            vec![
+                SnakePiece::Head(Direction::Up),
+                SnakePiece::Body(Direction::Up),
+                SnakePiece::Corner(Direction::Up),
+                SnakePiece::Body(Direction::Right),
+                SnakePiece::Tail(Direction::Left),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_create_food() {
         let config = Arc::new(Config {
             grid_size: (10, 10),
             ..Config::default()
         });
-        let food = Food::new(config.clone());
+        let food = Food::new(config.clone(), 0, &mut rand::rng());
         assert_eq!(food.position.0 < config.grid_size.0, true);
         assert_eq!(food.position.1 < config.grid_size.1, true);
     }
@@ -362,8 +865,42 @@ mod tests {
             grid_size: (1, 1),
             ..Config::default()
         });
-        let food = Food::new(config.clone());
+        let food = Food::new(config.clone(), 0, &mut rand::rng());
         assert_eq!(food.position.0, 0);
         assert_eq!(food.position.1, 0);
     }
+
+    #[test]
+    fn test_food_expire() {
+        let mut food = Food {
+            type_: String::from("Apple"),
+            position: Block(0, 0),
+            ticks_left: Some(2),
+        };
+        assert!(!food.expire());
+        assert!(food.expire());
+        food.ticks_left = None;
+        assert!(!food.expire());
+    }
+
+    #[test]
+    fn test_snake_shrink() {
+        let config = Arc::new(Config {
+            initial_size: 3,
+            ..Config::default()
+        });
+        let mut snake = Snake::new(config.clone());
+        snake.update(config.clone());
+        snake.update(config.clone());
+        snake.grow(2);
+        // The pending growth goes first
+        snake.shrink(3);
+        snake.update(config.clone());
+        assert_eq!(snake.body.len(), 2);
+        // Never shorter than the head
+        snake.shrink(10);
+        assert_eq!(snake.body.len(), 1);
+        snake.update(config.clone());
+        assert_eq!(snake.body.len(), 1);
+    }
 }
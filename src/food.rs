@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::effect::Effect;
+use crate::theme::Shape;
+
+pub const DEFAULT_FOOD_TYPES: &str = "resources/foods.json";
+
+///
+/// Kind of food, defined in the food types file
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoodType {
+    pub id: String,            // Name of the food, kept in the saves
+    pub texture: String,       // Sprite file name, looked up in the theme sprite directory
+    pub score: u32,            // Points when eaten
+    pub growth: u32,           // Blocks added to the snake when eaten
+    pub weight: u32,           // Chance to spawn, relative to the other types
+    pub lifetime: Option<u32>, // Ticks before the food disappears, forever if None
+    pub shape: Shape,          // Drawn over the food when shape markers are enabled
+    #[serde(default)]
+    pub effects: Vec<Effect>, // Applied when eaten, on top of the score and growth
+}
+
+///
+/// Every food type of the game
+///
+/// Loaded from a JSON list:
+/// `[{ "id": "Cherry", "texture": "cherry.png", "score": 1, "growth": 2,
+///     "weight": 60, "lifetime": null, "shape": "Circle", "effects": [] }]`
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<FoodType>", into = "Vec<FoodType>")]
+pub struct FoodTypes {
+    types: Vec<FoodType>,
+}
+
+impl Default for FoodTypes {
+    /// Built-in types, used when the food types file can't be loaded
+    fn default() -> FoodTypes {
+        let food_type = |id: &str, score, growth, weight, lifetime, shape, effects| FoodType {
+            id: id.to_string(),
+            texture: format!("{}.png", id.to_lowercase()),
+            score,
+            growth,
+            weight,
+            lifetime,
+            shape,
+            effects,
+        };
+        let speed = |percent, ticks| vec![Effect::Speed { percent, ticks }];
+        FoodTypes {
+            types: vec![
+                food_type("Cherry", 1, 2, 60, None, Shape::Circle, vec![]),
+                food_type("Banana", 3, 5, 30, None, Shape::Triangle, vec![]),
+                food_type("Apple", 5, 8, 10, Some(80), Shape::Diamond, vec![]),
+                food_type(
+                    "Poison",
+                    0,
+                    0,
+                    8,
+                    Some(60),
+                    Shape::Bar,
+                    vec![Effect::Shrink(4)],
+                ),
+                food_type("Pepper", 2, 1, 6, Some(60), Shape::Square, speed(60, 60)),
+                food_type("Clock", 1, 1, 6, Some(60), Shape::Ring, speed(160, 60)),
+                food_type(
+                    "Star",
+                    0,
+                    1,
+                    4,
+                    Some(40),
+                    Shape::Plus,
+                    vec![Effect::ScoreMultiplier {
+                        factor: 2,
+                        ticks: 100,
+                    }],
+                ),
+            ],
+        }
+    }
+}
+
+impl TryFrom<Vec<FoodType>> for FoodTypes {
+    type Error = String;
+
+    fn try_from(types: Vec<FoodType>) -> Result<Self, Self::Error> {
+        if types.is_empty() {
+            return Err(String::from("No food type"));
+        }
+        let mut ids = HashSet::new();
+        for food_type in &types {
+            if !ids.insert(food_type.id.as_str()) {
+                return Err(format!("Food type {} is defined twice", food_type.id));
+            }
+            if food_type.weight == 0 {
+                return Err(format!("Food type {} has a weight of 0", food_type.id));
+            }
+        }
+        Ok(FoodTypes { types })
+    }
+}
+impl From<FoodTypes> for Vec<FoodType> {
+    fn from(food_types: FoodTypes) -> Vec<FoodType> {
+        food_types.types
+    }
+}
+
+impl FoodTypes {
+    pub fn load(filename: &Path) -> Result<FoodTypes, String> {
+        let json = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", filename.display(), e))
+    }
+
+    /// Food types file of the game, the built-in types if it can't be loaded
+    pub fn load_default() -> FoodTypes {
+        FoodTypes::load(Path::new(DEFAULT_FOOD_TYPES)).unwrap_or_else(|e| {
+            println!("FoodTypes: Failed to load ({}), using built-in types", e);
+            FoodTypes::default()
+        })
+    }
+
+    pub fn all(&self) -> &[FoodType] {
+        &self.types
+    }
+
+    /// Food type by id, the first type if it doesn't exist (from an old save)
+    pub fn get(&self, id: &str) -> &FoodType {
+        self.types
+            .iter()
+            .find(|food_type| food_type.id == id)
+            .unwrap_or(&self.types[0])
+    }
+
+    ///
+    /// Change the weight of some types, such as the food mix of a difficulty
+    /// Weights of 0 and unknown ids are ignored
+    ///
+    pub fn with_weights(mut self, weights: &[(String, u32)]) -> FoodTypes {
+        for (id, weight) in weights {
+            match self.types.iter_mut().find(|food_type| food_type.id == *id) {
+                Some(food_type) if *weight > 0 => food_type.weight = *weight,
+                _ => println!("FoodTypes: Ignoring weight {} of {}", weight, id),
+            }
+        }
+        self
+    }
+
+    ///
+    /// Pick a random type, each type has a chance of its weight over the total
+    ///
+    pub fn pick(&self, rng: &mut impl Rng) -> &FoodType {
+        let total: u32 = self.types.iter().map(|food_type| food_type.weight).sum();
+        let mut value = rng.random_range(0..total);
+        for food_type in &self.types {
+            if value < food_type.weight {
+                return food_type;
+            }
+            value -= food_type.weight;
+        }
+        unreachable!("The random value is below the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_load_default_types() {
+        let food_types = FoodTypes::load(Path::new(DEFAULT_FOOD_TYPES)).unwrap();
+        assert_eq!(food_types, FoodTypes::default());
+    }
+
+    #[test]
+    fn test_validation() {
+        let types = |json: &str| serde_json::from_str::<FoodTypes>(json);
+        let cherry = r#"{"id": "Cherry", "texture": "cherry.png", "score": 1, "growth": 2,
+            "weight": 60, "lifetime": 50, "shape": "Circle"}"#;
+        assert!(types(&format!("[{}]", cherry)).is_ok());
+        assert!(types("[]").is_err());
+        assert!(types(&format!("[{}, {}]", cherry, cherry)).is_err());
+        assert!(types(&cherry.replace("60", "0")).is_err());
+    }
+
+    #[test]
+    fn test_pick() {
+        let basic = FoodTypes::default().all()[..3].to_vec();
+        let food_types = FoodTypes::try_from(basic).unwrap().with_weights(&[
+            (String::from("Cherry"), 1),
+            (String::from("Banana"), 0),
+            (String::from("Apple"), 3),
+        ]);
+        assert_eq!(food_types.get("Banana").weight, 30);
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = [0; 3];
+        for _ in 0..3400 {
+            let id = &food_types.pick(&mut rng).id;
+            counts[food_types.all().iter().position(|t| t.id == *id).unwrap()] += 1;
+        }
+        // 1, 30 and 3 out of 34
+        assert!((50..150).contains(&counts[0]), "{:?}", counts);
+        assert!((2800..3200).contains(&counts[1]), "{:?}", counts);
+        assert!((200..400).contains(&counts[2]), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_get_unknown() {
+        let food_types = FoodTypes::default();
+        assert_eq!(food_types.get("Apple").score, 5);
+        assert_eq!(food_types.get("Durian").id, "Cherry");
+    }
+}
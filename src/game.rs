@@ -1,44 +1,131 @@
 use std::{
-    sync::{Arc, Mutex},
-    time::Duration,
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rodio::OutputStream;
 use sdl2::{
     event::Event,
     image::{InitFlag, LoadTexture},
-    keyboard::Keycode,
-    pixels::Color,
-    rect::Rect,
-    render::WindowCanvas,
+    pixels::{Color, PixelFormatEnum},
+    rect::{Point, Rect},
+    render::{Texture, TextureCreator, WindowCanvas},
+    surface::Surface,
+    video::WindowContext,
     Sdl,
 };
 use snake::{
-    entity::{Config, Direction, Food, GameState, Snake},
-    savegame::{load_game_state, save_game_state},
+    campaign::{load_progress, save_progress, Campaign},
+    challenge::{date, load_daily_results, save_daily_results, today, Challenge},
+    difficulty::Difficulty,
+    effect::Effect,
+    food::FoodTypes,
+    gamepad::Gamepads,
+    hazard::Hazard,
+    highscore::{load_high_scores, save_high_scores, HighScore},
+    input::Action,
+    map::Map,
+    mode::{format_time, is_open, GameMode, SHRINK_PERIOD},
+    powerup::{pull, PowerUp, PowerUpKind, SPAWN_CHANCE},
+    savegame::delete_save,
+    settings::load_settings,
+    sound::{Playback, Sound, SoundSystem},
+    theme::{Shape, Theme},
+    timestep::FixedTimestep,
 };
 use snake::{
-    savegame::delete_save,
-    sound::{Sound, SoundSystem},
+    entity::{
+        Block, BorderMode, Config, ControlScheme, Direction, Food, GameState, Snake, SnakePiece,
+    },
+    savegame::{load_game_state, save_game_state},
 };
 
+use crate::ui::Column;
+use crate::WINDOW_SIZE;
+
+#[derive(Debug, Clone, PartialEq)]
+enum PauseChoice {
+    Action(Action), // Same as the key bound to the action
+    End,            // End a game that has no other end
+    NextStage,      // Go on to the next stage of the campaign
+}
+
+///
+/// Overlay of the paused game, zen games can be ended from there
+/// The daily challenge has no restart
+///
+fn pause_menu(config: &Config) -> Column<PauseChoice> {
+    let menu = Column::new(300, 200, 200, 50)
+        .label("Paused")
+        .button("Resume", PauseChoice::Action(Action::Pause));
+    let menu = match config.daily {
+        Some(_) => menu,
+        None => menu.button("Restart", PauseChoice::Action(Action::Restart)),
+    };
+    let menu = if config.mode.deadly() {
+        menu
+    } else {
+        menu.button("End game", PauseChoice::End)
+    };
+    menu.button("Main menu", PauseChoice::Action(Action::Quit))
+}
+
+///
+/// Transition screen between two campaign stages
+///
+fn stage_menu(config: &Config, cleared: usize) -> Column<PauseChoice> {
+    let menu = Column::new(250, 200, 300, 50).label(&format!("Stage {} cleared", cleared + 1));
+    let menu = match config.campaign.stage(cleared + 1) {
+        Some(next) => menu.label(&format!("Next: {}, eat {}", next.map, next.goal)),
+        None => menu,
+    };
+    menu.button("Continue", PauseChoice::NextStage)
+        .button("Main menu", PauseChoice::Action(Action::Quit))
+}
+
+/// Maximum number of direction changes waiting for the next ticks
+const MAX_QUEUED_INPUTS: usize = 3;
 
 pub struct Game {
     pub state: GameState,         // Game state
     pub config: Arc<Config>,      // Game config
     pub snd: Option<SoundSystem>, // Sound system
+    inputs: VecDeque<Direction>,  // Direction changes waiting for the next ticks
+    previous_body: Vec<Block>,    // Snake body before the last tick
+    pub alpha: f32,               // Fraction of the current tick elapsed, for rendering
+    pub paused: bool,             // No tick while paused
+    rng: StdRng,                  // Places the food and power-ups, seeded in daily challenges
+}
+
+///
+/// Random generator of a tick
+/// In a daily challenge it only depends on the seed and the tick,
+/// the same moves give the same food and power-ups to every player
+///
+fn tick_rng(config: &Config, ticks: u32) -> StdRng {
+    match config.daily {
+        Some(ref challenge) => StdRng::seed_from_u64(
+            challenge.seed ^ (ticks as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+        ),
+        None => StdRng::from_rng(&mut rand::rng()),
+    }
 }
 
 impl Game {
-    fn new(config: Arc<Config>, snd: Option<SoundSystem>, continue_game: bool) -> Self {
-        let mut state = GameState::new(config.clone());
-        if continue_game == true {
-            state = load_game_state().unwrap_or(state);
-        }
+    fn new(config: Arc<Config>, snd: Option<SoundSystem>, saved: Option<GameState>) -> Self {
+        let state = saved.unwrap_or_else(|| GameState::new(config.clone()));
+        let rng = tick_rng(&config, state.ticks);
         Game {
             state,
             config: Arc::clone(&config),
             snd,
+            inputs: VecDeque::new(),
+            previous_body: Vec::new(),
+            alpha: 0.0,
+            paused: false,
+            rng,
         }
     }
 
@@ -46,9 +133,65 @@ impl Game {
         println!("Game: Setup");
         println!("Game: Config={:?}", self.config);
         self.play_snd(Sound::Start);
+        self.rng = tick_rng(&self.config, self.state.ticks);
         self.create_food();
     }
 
+    ///
+    /// Go on to the next campaign stage, on the map of its config
+    /// The score is carried across, the snake starts over
+    ///
+    pub fn next_stage(&mut self, config: Arc<Config>) {
+        println!("Game: Stage {:?}", config.stage);
+        let score = self.state.score;
+        let best_combo = self.state.combo.best;
+        let elapsed = self.state.elapsed;
+        self.config = config;
+        self.state = GameState::new(self.config.clone());
+        self.state.score = score;
+        self.state.combo.best = best_combo;
+        self.state.elapsed = elapsed;
+        self.inputs.clear();
+        self.previous_body.clear();
+        self.paused = false;
+        self.setup();
+    }
+
+    /// Whether the campaign stage is cleared, and the game waits to go on to the next one
+    pub fn stage_cleared(&self) -> bool {
+        self.state.won && !self.state.game_over
+    }
+
+    /// Food to eat to clear the campaign stage
+    fn goal(&self) -> Option<u32> {
+        let stage = self.config.campaign.stage(self.state.stage?)?;
+        Some(stage.goal)
+    }
+
+    /// Start a new game with the same config
+    /// A daily challenge can't be restarted, there is one attempt per day
+    pub fn restart(&mut self) {
+        if self.state.daily.is_some() {
+            println!("Game: No restart in the daily challenge");
+            return;
+        }
+        println!("Game: Restart");
+        self.state = GameState::new(self.config.clone());
+        self.inputs.clear();
+        self.previous_body.clear();
+        self.paused = false;
+        self.setup();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.state.game_over || self.stage_cleared() {
+            return;
+        }
+        self.paused = !self.paused;
+        println!("Game: Paused={}", self.paused);
+        self.play_snd(Sound::Pause);
+    }
+
     ///
     /// Handle food collision
     ///
@@ -58,64 +201,377 @@ impl Game {
         }
 
         if let Some(ref food) = self.state.food {
-            self.state.snake.grow(food.type_.increase());
-            self.state.score += food.type_.score();
-            self.play_snd(Sound::Eat);
-            self.state.level = self.calculate_level();
+            let config = self.config.clone();
+            let food_type = config.food_types.get(&food.type_);
+            self.state.snake.grow(food_type.growth);
+            let combo = self.state.combo.eat();
+            self.state.score += food_type.score * self.state.effects.score_multiplier() * combo;
+            self.state.eaten += 1;
+            for effect in &food_type.effects {
+                println!("Game: Effect {:?}", effect);
+                match *effect {
+                    Effect::Shrink(count) => self.state.snake.shrink(count),
+                    _ => self.state.effects.add(*effect),
+                }
+            }
+            let level = self.calculate_level();
+            self.play_snd_with(Sound::Eat, self.eat_playback(food.position.0, level));
+            if level > self.state.level {
+                self.play_snd(Sound::LevelUp);
+            }
+            self.state.level = level;
             self.state.speed = self.calculate_speed();
 
             self.create_food();
         }
     }
 
+    ///
+    /// Shrinking arena: score the tick survived, and close a ring when it's time
+    /// The food and power-up caught in the closed ring move back into the arena
+    ///
+    fn handle_arena(&mut self) {
+        if self.state.mode != GameMode::Shrinking {
+            return;
+        }
+        self.state.score += 1;
+        if !self.state.ticks.is_multiple_of(SHRINK_PERIOD) {
+            return;
+        }
+        let rings = self.closed_rings();
+        println!("Game: Arena closed to {} rings", rings);
+        self.play_snd(Sound::LevelUp);
+        self.state.level = self.calculate_level();
+        self.state.speed = self.calculate_speed();
+
+        let open = |block: &Block| is_open(self.config.grid_size, rings, block);
+        let food_closed = self.state.food.as_ref().is_some_and(|f| !open(&f.position));
+        let power_up_closed = self
+            .state
+            .power_up
+            .as_ref()
+            .is_some_and(|p| !open(&p.position));
+        if food_closed {
+            self.create_food();
+        }
+        if power_up_closed {
+            self.state.power_up = None;
+        }
+    }
+
+    /// Outer rings of the grid turned into walls
+    fn closed_rings(&self) -> u32 {
+        self.state.mode.closed_rings(self.state.ticks)
+    }
+
+    /// Replace the food at the end of its lifetime
+    fn handle_food_expire(&mut self) {
+        if self.state.food.as_mut().is_some_and(|food| food.expire()) {
+            self.play_snd(Sound::FoodExpire);
+            self.create_food();
+        }
+    }
+
+    ///
+    /// Pick up, expire or spawn the power-up, and pull the food with the magnet
+    ///
+    fn handle_power_ups(&mut self) {
+        let head = *self.state.snake.head();
+        if let Some(ref mut power_up) = self.state.power_up {
+            if power_up.position == head {
+                println!("Game: Power-up {:?}", power_up.kind);
+                self.state.power_ups.activate(power_up.kind);
+                self.state.power_up = None;
+                self.play_snd(Sound::PowerUp);
+            } else if power_up.expire() {
+                self.state.power_up = None;
+            }
+        } else if self.rng.random_ratio(1, SPAWN_CHANCE) {
+            let snake = &self.state.snake;
+            let food = self.state.food.as_ref().map(|food| food.position);
+            let rings = self.closed_rings();
+            self.state.power_up = PowerUp::spawn(&mut self.rng, self.config.grid_size, |b| {
+                snake.body.contains(b)
+                    || food == Some(*b)
+                    || self.config.map.blocked(b)
+                    || !is_open(self.config.grid_size, rings, b)
+            });
+        }
+
+        if self.state.power_ups.magnet > 0 {
+            // The food is only pulled onto blocks it could have spawned on
+            let rings = self.closed_rings();
+            let hazards: Vec<Block> = self.state.hazards.iter().flat_map(|h| h.blocks()).collect();
+            if let Some(ref mut food) = self.state.food {
+                match pull(&food.position, &head) {
+                    Some(position)
+                        if !self.state.snake.body.contains(&position)
+                            && !self.config.map.blocked(&position)
+                            && is_open(self.config.grid_size, rings, &position)
+                            && !hazards.contains(&position) =>
+                    {
+                        food.position = position
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
     /// Handle snake collision
     ///
-    /// The snake can't collide with itself
+    /// The snake can't collide with itself, unless in ghost mode
+    /// Hazards kill the snake when they touch any part of it, even in ghost mode
     fn handle_collisions(&mut self) {
+        let body = &self.state.snake.body;
+        if self
+            .state
+            .hazards
+            .iter()
+            .any(|hazard| hazard.blocks().iter().any(|block| body.contains(block)))
+        {
+            self.fatal_collision("Collision with a hazard");
+            return;
+        }
+        if self.state.power_ups.ghost > 0 {
+            return;
+        }
         let head = self.state.snake.head();
-        for block in self.state.snake.body.iter().skip(1) {
-            if head == block {
-                println!("Game: Collision with the snake body");
-                self.state.game_over = true;
-                self.play_snd(Sound::GameOver);
-                delete_save().expect("Failed to delete save game");
+        if self
+            .state
+            .snake
+            .body
+            .iter()
+            .skip(1)
+            .any(|block| head == block)
+        {
+            self.fatal_collision("Collision with the snake body");
+        }
+    }
+
+    /// End the game, unless the shield absorbs the collision or nothing kills in this mode
+    fn fatal_collision(&mut self, reason: &str) {
+        if !self.state.mode.deadly() {
+            println!("Game: {}, ignored in {}", reason, self.state.mode.label());
+        } else if self.state.power_ups.use_shield() {
+            println!("Game: {}, absorbed by the shield", reason);
+            self.play_snd(Sound::Shield);
+        } else {
+            self.end_game(reason);
+        }
+    }
+
+    fn end_game(&mut self, reason: &str) {
+        println!("Game: {}", reason);
+        self.state.game_over = true;
+        self.play_snd_with(Sound::GameOver, self.game_over_playback());
+        delete_save().expect("Failed to delete save game");
+        self.record_high_score();
+    }
+
+    /// End a game that only ends when the player wants to, such as zen
+    pub fn finish(&mut self) {
+        if !self.state.game_over {
+            self.end_game("Ended by the player");
+        }
+    }
+
+    /// Text shown at the end of the game
+    fn end_text(&self) -> &'static str {
+        match self.state.mode {
+            GameMode::TimeAttack if self.state.won => "Time's up",
+            GameMode::Race if self.state.won => "Finished",
+            GameMode::Zen => "Well played",
+            GameMode::Campaign if self.state.won => "Campaign complete",
+            _ => "Game Over",
+        }
+    }
+
+    ///
+    /// A race only counts when the snake reached the length
+    /// The daily challenge has its own results
+    ///
+    fn record_high_score(&self) {
+        if let Some(day) = self.state.daily {
+            self.record_daily_result(day);
+            return;
+        }
+        if self.state.mode.target_length().is_some() && !self.state.won {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut high_scores = load_high_scores();
+        let rank = high_scores.add(HighScore {
+            name: self.config.player_name.clone(),
+            score: self.state.score,
+            level: self.state.level,
+            difficulty: self.state.difficulty.clone(),
+            best_combo: self.state.combo.best,
+            timestamp,
+            mode: self.state.mode,
+            time_ms: self.state.elapsed,
+        });
+        if let Some(rank) = rank {
+            println!("Game: New high score, rank {}", rank + 1);
+            if let Err(e) = save_high_scores(&high_scores) {
+                println!("Game: Failed to save the high scores: {}", e);
             }
         }
     }
 
-    pub fn tick(&mut self) -> u32 {
+    fn record_daily_result(&self, day: u64) {
+        let mut results = load_daily_results();
+        let state = &self.state;
+        if results.finish(day, state.score, state.level, state.elapsed) {
+            println!("Game: Daily challenge of {} ended", date(day));
+            if let Err(e) = save_daily_results(&results) {
+                println!("Game: Failed to save the daily results: {}", e);
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.state.effects.tick();
+        self.state.combo.tick();
+        self.state.power_ups.tick();
+        self.state.ticks += 1;
+        if self.config.daily.is_some() {
+            self.rng = tick_rng(&self.config, self.state.ticks);
+        }
+        self.state.elapsed += self.tick_duration().as_millis() as u64;
+        if self
+            .state
+            .mode
+            .time_limit()
+            .is_some_and(|limit| self.state.elapsed >= limit)
+        {
+            self.state.won = true;
+            self.end_game("Time is up");
+            return;
+        }
+        self.handle_arena();
+
+        // Apply a single queued direction per tick, so two quick key presses
+        // can't turn the snake back on itself
+        if let Some(direction) = self.inputs.pop_front() {
+            if self.state.snake.cd(direction) {
+                self.play_snd(Sound::Turn);
+            }
+        }
+
+        // With a shield the snake goes through the wall and wraps around
+        if self.config.border_mode == BorderMode::Walls
+            && self.state.snake.leaving_grid(self.config.grid_size)
+        {
+            self.fatal_collision("Collision with the wall");
+            if self.state.game_over {
+                return;
+            }
+        }
+
+        self.previous_body = self.state.snake.body.clone();
         self.state.snake.update(self.config.clone());
 
+        if self.config.map.wall(self.state.snake.head()) {
+            self.fatal_collision("Collision with a wall of the map");
+            if self.state.game_over {
+                return;
+            }
+        }
+        if !is_open(
+            self.config.grid_size,
+            self.closed_rings(),
+            self.state.snake.head(),
+        ) {
+            self.fatal_collision("Collision with the closed arena");
+            if self.state.game_over {
+                return;
+            }
+        }
+        for hazard in &mut self.state.hazards {
+            hazard.tick();
+        }
+
         println!(
             "Game: Tick (score={} level={} speed={})",
             self.state.score, self.state.level, self.state.speed
         );
 
         self.handle_food_eat();
+        self.handle_food_expire();
+        self.handle_power_ups();
         self.handle_collisions();
 
-        if self.state.game_over {
-            return 0;
+        let length = self.state.snake.body.len();
+        if !self.state.game_over
+            && self
+                .state
+                .mode
+                .target_length()
+                .is_some_and(|target| length >= target)
+        {
+            self.state.won = true;
+            self.end_game("Reached the target length");
+        }
+        if !self.state.game_over && self.goal().is_some_and(|goal| self.state.eaten >= goal) {
+            self.clear_stage();
         }
-        self.state.speed
     }
 
-    pub fn keypress(&mut self, key: Keycode) {
-        match key {
-            Keycode::Up => self.state.snake.cd(Direction::Up),
-            Keycode::W => self.state.snake.cd(Direction::Up),
-            Keycode::Down => self.state.snake.cd(Direction::Down),
-            Keycode::S => self.state.snake.cd(Direction::Down),
-            Keycode::Left => self.state.snake.cd(Direction::Left),
-            Keycode::A => self.state.snake.cd(Direction::Left),
-            Keycode::Right => self.state.snake.cd(Direction::Right),
-            Keycode::D => self.state.snake.cd(Direction::Right),
-            _ => {}
+    ///
+    /// Campaign stage cleared, the campaign ends after the last one
+    ///
+    fn clear_stage(&mut self) {
+        self.state.won = true;
+        self.play_snd(Sound::LevelUp);
+        let next = self.state.stage.map_or(0, |stage| stage + 1);
+        if self.config.campaign.stage(next).is_none() {
+            self.end_game("Campaign complete");
+        } else {
+            println!("Game: Stage {} cleared", next);
+        }
+    }
+
+    /// Time between two ticks, changed by the speed effects
+    pub fn tick_duration(&self) -> Duration {
+        let speed = self.state.speed * self.state.effects.speed_percent() / 100;
+        Duration::from_millis(speed.max(1) as u64)
+    }
+
+    /// Queue a direction change
+    pub fn input(&mut self, action: Action) {
+        let direction = match self.state.control_scheme {
+            ControlScheme::Absolute => match action {
+                Action::Up => Direction::Up,
+                Action::Down => Direction::Down,
+                Action::Left => Direction::Left,
+                Action::Right => Direction::Right,
+                _ => return,
+            },
+            ControlScheme::Relative => {
+                // Turn from the heading the snake will have once the queue is applied
+                let heading = match self.inputs.back() {
+                    Some(direction) => *direction,
+                    None => self.state.snake.direction(),
+                };
+                match action {
+                    Action::Left => heading.turn_left(),
+                    Action::Right => heading.turn_right(),
+                    _ => return,
+                }
+            }
+        };
+        if self.inputs.back() != Some(&direction) && self.inputs.len() < MAX_QUEUED_INPUTS {
+            self.inputs.push_back(direction);
         }
     }
 
     pub fn create_food(&mut self) {
-        self.state.food = Some(Food::new(self.config.clone()));
+        let food = Food::new(self.config.clone(), self.closed_rings(), &mut self.rng);
+        self.state.food = Some(food);
     }
 
     pub fn eating_food(&mut self) -> bool {
@@ -127,10 +583,43 @@ impl Game {
 
     /// Play a sound
     pub fn play_snd(&self, sound: Sound) {
+        self.play_snd_with(sound, Playback::default());
+    }
+
+    /// Play a sound with a custom panning / pitch
+    pub fn play_snd_with(&self, sound: Sound, playback: Playback) {
         println!("Game: Play sound {:?}", sound);
-        match self.snd {
-            None => return,
-            Some(ref snd) => snd.play_snd(sound).expect("Failed to play sound"),
+        if let Some(ref snd) = self.snd {
+            snd.play_snd_with(sound, playback)
+                .expect("Failed to play sound");
+        }
+    }
+
+    ///
+    /// Playback of the eat sound
+    /// Panned with the food column, the pitch rises with the level
+    ///
+    fn eat_playback(&self, column: u32, level: u32) -> Playback {
+        let pan = if self.config.grid_size.0 > 1 {
+            column as f32 / (self.config.grid_size.0 - 1) as f32 * 2.0 - 1.0
+        } else {
+            0.0
+        };
+        Playback {
+            pan,
+            pitch: (1.0 + level as f32 * 0.05).min(2.0),
+        }
+    }
+
+    ///
+    /// Playback of the game over sound
+    /// The longer the snake, the lower the pitch
+    ///
+    fn game_over_playback(&self) -> Playback {
+        let length = self.state.snake.body.len() as f32;
+        Playback {
+            pitch: (1.2 - length * 0.01).max(0.5),
+            ..Playback::default()
         }
     }
 
@@ -139,108 +628,652 @@ impl Game {
     /// A level is gained every 10 points
     ///
     fn calculate_level(&self) -> u32 {
-        self.state.score / self.config.score_per_level
+        match self.state.mode {
+            // The score goes up every tick, the level with the arena
+            GameMode::Shrinking => self.closed_rings(),
+            // One level per campaign stage
+            GameMode::Campaign => self.state.stage.map_or(0, |stage| stage as u32),
+            _ => self.state.score / self.config.score_per_level,
+        }
     }
 
     ///
     /// Calculate the speed of the game based on the score
-    /// The speed is increased every level
+    /// The speed goes up with the level, following the curve of the difficulty
     ///
     fn calculate_speed(&self) -> u32 {
-        let level = self.calculate_level();
-        let speed: i32 = self.config.initial_speed as i32
-            - (level as i32 * self.config.speed_increase_per_level as i32);
-        if speed < self.config.maximum_speed as i32 {
-            self.config.maximum_speed
-        } else {
-            speed as u32
+        if !self.state.mode.speeds_up() {
+            return self.config.initial_speed;
         }
+        self.config.speed_curve.speed(
+            self.calculate_level(),
+            self.config.initial_speed,
+            self.config.speed_increase_per_level,
+            self.config.maximum_speed,
+        )
     }
 
     ///
     /// Get the status text of the game
     ///
     fn get_status_text(&self) -> String {
-        format!("Score: {} Level: {}", self.state.score, self.state.level)
+        let mut text = format!("Score: {} Level: {}", self.state.score, self.state.level);
+        if let Some(ticks) = self.state.mode.next_ring_in(self.state.ticks) {
+            text.push_str(&format!(" Ring: {}", ticks));
+        }
+        if let Some(limit) = self.state.mode.time_limit() {
+            let left = limit.saturating_sub(self.state.elapsed);
+            text.push_str(&format!(" Time: {}", format_time(left)));
+        }
+        if let Some(target) = self.state.mode.target_length() {
+            let length = self.state.snake.body.len();
+            let time = format_time(self.state.elapsed);
+            text.push_str(&format!(" Length: {}/{} Time: {}", length, target, time));
+        }
+        if let (Some(stage), Some(goal)) = (self.state.stage, self.goal()) {
+            let eaten = self.state.eaten;
+            text.push_str(&format!(" Stage: {} Food: {}/{}", stage + 1, eaten, goal));
+        }
+        if self.state.combo.multiplier > 1 {
+            text.push_str(&format!(" Combo: x{}", self.state.combo.multiplier));
+        }
+        for active in self.state.effects.iter() {
+            text.push_str(&format!(" [{}]", active.label()));
+        }
+        for label in self.state.power_ups.labels() {
+            text.push_str(&format!(" [{}]", label));
+        }
+        text
     }
 }
 
 trait Drawable {
     fn draw(&self, canvas: &mut WindowCanvas, config: Arc<Config>);
 }
-impl Drawable for Snake {
-    fn draw(&self, canvas: &mut WindowCanvas, config: Arc<Config>) {
-        let color = &config.snake_color;
 
-        for block in &self.body {
-            canvas.set_draw_color(*color);
+///
+/// Draw the snake, with the sprite sheet loaded once by the game loop
+///
+fn draw_snake(
+    snake: &Snake,
+    sprites: Option<&Texture>,
+    canvas: &mut WindowCanvas,
+    config: Arc<Config>,
+) {
+    for (block, piece) in snake.body.iter().zip(snake.pieces(config.clone())) {
+        let x = block.0 as i32 * config.grid_resolution as i32;
+        let y = block.1 as i32 * config.grid_resolution as i32;
+        draw_segment(canvas, sprites, piece, x, y, &config);
+    }
+}
+
+///
+/// Textures of the theme, loaded once per game instead of every frame
+/// A missing texture falls back to the flat color renderer
+///
+struct Sprites<'a> {
+    snake: Option<Texture<'a>>,                   // Snake sprite sheet
+    food: HashMap<String, Texture<'a>>,           // Food textures, by file name
+    power_ups: HashMap<PowerUpKind, Texture<'a>>, // Power-up textures
+}
+
+impl<'a> Sprites<'a> {
+    fn load(t: &'a TextureCreator<WindowContext>, config: &Config) -> Sprites<'a> {
+        let food = config
+            .food_types
+            .all()
+            .iter()
+            .filter_map(|food_type| {
+                let texture = load_sprite(t, config, &food_type.texture)?;
+                Some((food_type.texture.clone(), texture))
+            })
+            .collect();
+        let power_ups = PowerUpKind::all()
+            .into_iter()
+            .filter_map(|kind| Some((kind, load_sprite(t, config, kind.texture())?)))
+            .collect();
+        Sprites {
+            snake: load_sprite(t, config, "snake.png"),
+            food,
+            power_ups,
+        }
+    }
+}
+
+/// Load a sprite of the theme, None if the theme has none or it can't be loaded
+fn load_sprite<'a>(
+    t: &'a TextureCreator<WindowContext>,
+    config: &Config,
+    filename: &str,
+) -> Option<Texture<'a>> {
+    let path = config.theme.sprite(filename)?;
+    t.load_texture(path)
+        .map_err(|e| println!("Game: Failed to load sprite {}: {}", filename, e))
+        .ok()
+}
+
+///
+/// Draw a snake segment at a pixel position
+///
+/// The sprite sheet has 4 square tiles facing up: head, body, corner and tail.
+///
+fn draw_segment(
+    canvas: &mut WindowCanvas,
+    sprites: Option<&Texture>,
+    piece: SnakePiece,
+    x: i32,
+    y: i32,
+    config: &Config,
+) {
+    let dest = Rect::new(x, y, config.grid_resolution, config.grid_resolution);
+    let Some(sprites) = sprites else {
+        canvas.set_draw_color(config.theme.snake);
+        let _ = canvas.fill_rect(dest);
+        return;
+    };
+
+    let (index, direction) = match piece {
+        SnakePiece::Head(d) => (0, d),
+        SnakePiece::Body(d) => (1, d),
+        SnakePiece::Corner(d) => (2, d),
+        SnakePiece::Tail(d) => (3, d),
+    };
+    let angle = match direction {
+        Direction::Up => 0.0,
+        Direction::Right => 90.0,
+        Direction::Down => 180.0,
+        Direction::Left => 270.0,
+    };
+    let tile = sprites.query().height;
+    let src = Rect::new(index * tile as i32, 0, tile, tile);
+    let _ = canvas.copy_ex(sprites, src, dest, angle, None, false, false);
+}
+
+///
+/// Draw the snake between its previous and current position
+///
+/// A segment crossing an edge of the grid is drawn on both sides.
+///
+fn draw_snake_interpolated(
+    snake: &Snake,
+    previous: &[Block],
+    alpha: f32,
+    sprites: Option<&Texture>,
+    canvas: &mut WindowCanvas,
+    config: Arc<Config>,
+) {
+    let resolution = config.grid_resolution as f32;
+    let (width, height) = (config.grid_size.0 as f32, config.grid_size.1 as f32);
 
-            let x = block.0 as i32 * config.grid_resolution as i32;
-            let y = block.1 as i32 * config.grid_resolution as i32;
+    let positions = snake.interpolate(previous, alpha, config.clone());
+    for ((x, y), piece) in positions.into_iter().zip(snake.pieces(config.clone())) {
+        let mut xs = vec![x];
+        if x < 0.0 {
+            xs.push(x + width);
+        } else if x > width - 1.0 {
+            xs.push(x - width);
+        }
+        let mut ys = vec![y];
+        if y < 0.0 {
+            ys.push(y + height);
+        } else if y > height - 1.0 {
+            ys.push(y - height);
+        }
+        for x in &xs {
+            for y in &ys {
+                draw_segment(
+                    canvas,
+                    sprites,
+                    piece,
+                    (x * resolution).round() as i32,
+                    (y * resolution).round() as i32,
+                    &config,
+                );
+            }
+        }
+    }
+}
 
-            let _ = canvas.fill_rect(Rect::new(
-                x,
-                y,
-                config.grid_resolution,
-                config.grid_resolution,
-            ));
+/// Draw the food with its texture, or in the food color of the theme without one
+fn draw_food(food: &Food, sprites: &Sprites, canvas: &mut WindowCanvas, config: Arc<Config>) {
+    let x = food.position.0 as i32 * config.grid_resolution as i32;
+    let y = food.position.1 as i32 * config.grid_resolution as i32;
+    let r = Rect::new(x, y, config.grid_resolution, config.grid_resolution);
+    let food_type = config.food_types.get(&food.type_);
+    match sprites.food.get(&food_type.texture) {
+        Some(texture) => {
+            let _ = canvas.copy(texture, None, r);
+        }
+        None => {
+            canvas.set_draw_color(config.theme.food);
+            let _ = canvas.fill_rect(r);
         }
     }
+    if config.shape_markers {
+        draw_shape(canvas, food_type.shape, x, y, &config);
+    }
 }
-impl Drawable for Food {
+
+///
+/// Hazards are drawn like the walls, with a cross so they can be told apart
+///
+impl Drawable for Hazard {
     fn draw(&self, canvas: &mut WindowCanvas, config: Arc<Config>) {
-        let x = self.position.0 as i32 * config.grid_resolution as i32;
-        let y = self.position.1 as i32 * config.grid_resolution as i32;
-        let t = canvas.texture_creator();
-        let filename = self.type_.texture();
-        let tex = t.load_texture(filename).unwrap();
-        let r = Rect::new(x, y, config.grid_resolution, config.grid_resolution);
-        canvas.copy(&tex, None, r).unwrap();
+        let resolution = config.grid_resolution;
+        for block in self.blocks() {
+            let (x, y) = ((block.0 * resolution) as i32, (block.1 * resolution) as i32);
+            canvas.set_draw_color(config.theme.wall);
+            let _ = canvas.fill_rect(Rect::new(x, y, resolution, resolution));
+            draw_shape(canvas, Shape::Cross, x, y, &config);
+        }
     }
 }
 
-impl Drawable for Game {
-    fn draw(&self, canvas: &mut WindowCanvas, config: Arc<Config>) {
-        let color = &config.background_color;
-        canvas.set_draw_color(*color);
+/// Draw the power-up with its texture, or in the power-up color of the theme without one
+fn draw_power_up(
+    power_up: &PowerUp,
+    sprites: &Sprites,
+    canvas: &mut WindowCanvas,
+    config: Arc<Config>,
+) {
+    let x = power_up.position.0 as i32 * config.grid_resolution as i32;
+    let y = power_up.position.1 as i32 * config.grid_resolution as i32;
+    let r = Rect::new(x, y, config.grid_resolution, config.grid_resolution);
+    match sprites.power_ups.get(&power_up.kind) {
+        Some(texture) => {
+            let _ = canvas.copy(texture, None, r);
+        }
+        None => {
+            canvas.set_draw_color(config.theme.power_up);
+            let _ = canvas.fill_rect(r);
+        }
+    }
+    if config.shape_markers {
+        draw_shape(canvas, power_up.kind.shape(), x, y, &config);
+    }
+}
+
+///
+/// Draw a shape marker in a cell, in the background color
+///
+fn draw_shape(canvas: &mut WindowCanvas, shape: Shape, x: i32, y: i32, config: &Config) {
+    // Keep a margin so the marker stays inside the object
+    let margin = (config.grid_resolution / 5) as i32;
+    let size = config
+        .grid_resolution
+        .saturating_sub(2 * margin as u32)
+        .max(1);
+    let points: Vec<Point> = shape
+        .mask(size)
+        .into_iter()
+        .map(|(px, py)| Point::new(x + margin + px, y + margin + py))
+        .collect();
+    canvas.set_draw_color(config.theme.background);
+    let _ = canvas.draw_points(points.as_slice());
+}
+
+impl Game {
+    fn draw(&self, canvas: &mut WindowCanvas, config: Arc<Config>, sprites: &Sprites) {
+        canvas.set_draw_color(config.theme.background);
         canvas.clear();
-        self.state.snake.draw(canvas, config.clone());
+        if let Some(color) = config.theme.grid_lines {
+            draw_grid_lines(canvas, color.into(), &config);
+        }
+        if config.border_mode == BorderMode::Walls {
+            draw_walls(canvas, &config);
+        }
+        draw_map(canvas, &config);
+        draw_arena(canvas, self.closed_rings(), &config);
+        if config.smooth_movement && !self.state.game_over {
+            draw_snake_interpolated(
+                &self.state.snake,
+                &self.previous_body,
+                self.alpha,
+                sprites.snake.as_ref(),
+                canvas,
+                config.clone(),
+            );
+        } else {
+            draw_snake(
+                &self.state.snake,
+                sprites.snake.as_ref(),
+                canvas,
+                config.clone(),
+            );
+        }
         if let Some(ref food) = self.state.food {
-            food.draw(canvas, config.clone());
+            draw_food(food, sprites, canvas, config.clone());
+        }
+        if let Some(ref power_up) = self.state.power_up {
+            draw_power_up(power_up, sprites, canvas, config.clone());
+        }
+        for hazard in &self.state.hazards {
+            hazard.draw(canvas, config.clone());
+        }
+    }
+}
+
+///
+/// Draw the walls along the edge of the grid
+///
+fn draw_walls(canvas: &mut WindowCanvas, config: &Config) {
+    let resolution = config.grid_resolution;
+    let width = config.grid_size.0 * resolution;
+    let height = config.grid_size.1 * resolution;
+    let thickness = (resolution / 4).max(1);
+    canvas.set_draw_color(config.theme.wall);
+    let _ = canvas.fill_rects(&[
+        Rect::new(0, 0, width, thickness),
+        Rect::new(0, (height - thickness) as i32, width, thickness),
+        Rect::new(0, 0, thickness, height),
+        Rect::new((width - thickness) as i32, 0, thickness, height),
+    ]);
+}
+
+///
+/// Draw the closed rings of the shrinking arena, like the walls
+///
+fn draw_arena(canvas: &mut WindowCanvas, rings: u32, config: &Config) {
+    if rings == 0 {
+        return;
+    }
+    let resolution = config.grid_resolution;
+    let (width, height) = config.grid_size;
+    let thickness = rings.min(width / 2 + 1).min(height / 2 + 1) * resolution;
+    let (width, height) = (width * resolution, height * resolution);
+    canvas.set_draw_color(config.theme.wall);
+    let _ = canvas.fill_rects(&[
+        Rect::new(0, 0, width, thickness),
+        Rect::new(0, height.saturating_sub(thickness) as i32, width, thickness),
+        Rect::new(0, 0, thickness, height),
+        Rect::new(width.saturating_sub(thickness) as i32, 0, thickness, height),
+    ]);
+}
+
+/// Colors of the portal pairs, the two ends of a pair have the same color
+const PORTAL_COLORS: [Color; 4] = [
+    Color::RGB(0, 158, 255),
+    Color::RGB(255, 140, 0),
+    Color::RGB(200, 60, 220),
+    Color::RGB(0, 200, 120),
+];
+
+///
+/// Draw the walls and the portals of the map
+/// Portals are rings, in the background color, over the color of their pair
+///
+fn draw_map(canvas: &mut WindowCanvas, config: &Config) {
+    let resolution = config.grid_resolution;
+    let cell = |block: &Block| {
+        Rect::new(
+            (block.0 * resolution) as i32,
+            (block.1 * resolution) as i32,
+            resolution,
+            resolution,
+        )
+    };
+    canvas.set_draw_color(config.theme.wall);
+    let walls: Vec<Rect> = config.map.walls.iter().map(cell).collect();
+    let _ = canvas.fill_rects(&walls);
+    for (i, (a, b)) in config.map.portals.iter().enumerate() {
+        canvas.set_draw_color(PORTAL_COLORS[i % PORTAL_COLORS.len()]);
+        let _ = canvas.fill_rects(&[cell(a), cell(b)]);
+        for end in [a, b] {
+            let (x, y) = (cell(end).x(), cell(end).y());
+            draw_shape(canvas, Shape::Ring, x, y, config);
+        }
+    }
+}
+
+///
+/// Draw a line between every cell of the grid
+///
+fn draw_grid_lines(canvas: &mut WindowCanvas, color: Color, config: &Config) {
+    let resolution = config.grid_resolution as i32;
+    let width = config.grid_size.0 as i32 * resolution;
+    let height = config.grid_size.1 as i32 * resolution;
+    canvas.set_draw_color(color);
+    for x in 1..config.grid_size.0 as i32 {
+        let _ = canvas.draw_line((x * resolution, 0), (x * resolution, height));
+    }
+    for y in 1..config.grid_size.1 as i32 {
+        let _ = canvas.draw_line((0, y * resolution), (width, y * resolution));
+    }
+}
+
+///
+/// Save the content of the canvas to a bmp file
+///
+fn save_screenshot(canvas: &WindowCanvas) -> Result<String, String> {
+    let (width, height) = canvas.output_size()?;
+    let mut pixels = canvas.read_pixels(None, PixelFormatEnum::RGB24)?;
+    let surface = Surface::from_data(
+        &mut pixels,
+        width,
+        height,
+        width * 3,
+        PixelFormatEnum::RGB24,
+    )?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    let filename = format!("screenshot-{}.bmp", timestamp);
+    surface.save_bmp(&filename)?;
+    Ok(filename)
+}
+
+/// Pixels per block, so the grid fits in the window
+fn grid_resolution(grid_size: (u32, u32)) -> u32 {
+    (WINDOW_SIZE.0 / grid_size.0)
+        .min(WINDOW_SIZE.1 / grid_size.1)
+        .max(1)
+}
+
+///
+/// Map to play on, without a map if it can't be found
+///
+fn find_map(name: Option<String>) -> Map {
+    name.and_then(|name| {
+        Map::find(&name).or_else(|| {
+            println!("Game: Map {} not found, playing without a map", name);
+            None
+        })
+    })
+    .unwrap_or_default()
+}
+
+///
+/// Config of the next campaign stage, on its own map and grid
+///
+fn next_stage_config(config: &Config) -> Config {
+    let stage = config.stage.map_or(0, |stage| stage + 1);
+    let map = find_map(config.campaign.stage(stage).map(|stage| stage.map.clone()));
+    let grid_size = if map.name.is_empty() {
+        config.grid_size
+    } else {
+        map.grid_size
+    };
+    Config {
+        grid_size,
+        grid_resolution: grid_resolution(grid_size),
+        starting_position: (grid_size.0 / 2, grid_size.1 / 2),
+        map,
+        stage: Some(stage),
+        ..config.clone()
+    }
+}
+
+///
+/// Run the ticks due after a frame, returns how many ran
+///
+/// No time is kept while the game is paused, over or between two stages,
+/// so resuming doesn't run a burst of ticks at once.
+///
+fn run_ticks(game: &mut Game, timestep: &mut FixedTimestep, elapsed: Duration) -> u32 {
+    if game.paused || game.state.game_over || game.stage_cleared() {
+        timestep.reset();
+        return 0;
+    }
+    timestep.advance(elapsed, game.tick_duration());
+    // The tick duration may change after each tick
+    let mut ticks = 0;
+    while !game.state.game_over && !game.stage_cleared() && timestep.consume(game.tick_duration()) {
+        game.tick();
+        ticks += 1;
+    }
+    ticks
+}
+
+///
+/// Unlock the next campaign stage and go on to it
+/// Returns the config of the stage
+///
+fn advance_stage(game: &mut Game) -> Arc<Config> {
+    let config = Arc::new(next_stage_config(&game.config));
+    let mut progress = load_progress();
+    if config.stage.is_some_and(|stage| progress.unlock(stage)) {
+        if let Err(e) = save_progress(&progress) {
+            println!("Game: Failed to save the campaign progress: {}", e);
         }
     }
+    game.next_stage(config.clone());
+    config
 }
 
-// impl From<Color> for sdl2::pixels::Color {
-//     fn from(color: Color) -> sdl2::pixels::Color {
-//         sdl2::pixels::Color::RGB(color.r, color.g, color.b)
-//     }
-// }
+///
+/// How a game is started from the menus
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Start {
+    New,             // Mode and map of the settings
+    Continue,        // The saved game, a new one if there is none
+    Campaign(usize), // Campaign from a stage
+    Daily,           // Challenge of the day
+}
 
 ///
 /// Main game loop
 ///
-pub fn run(
-    sdl_context: &Sdl,
-    canvas: &mut WindowCanvas,
-    continue_game: bool,
-) -> Result<(), String> {
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas, start: Start) -> Result<(), String> {
     // let video_subsystem = sdl_context.video().unwrap();
-    let timer_subsystem = sdl_context.timer()?;
     let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
     let _image_context = sdl2::image::init(InitFlag::PNG)?;
 
-    let mut font = ttf_context.load_font("resources/COUR.TTF", 20)?;
-    font.set_style(sdl2::ttf::FontStyle::BOLD);
-
+    let settings = load_settings();
+    let saved = if start == Start::Continue {
+        load_game_state()
+            .map_err(|e| println!("Game: Failed to load the save ({}), new game", e))
+            .ok()
+    } else {
+        None
+    };
+    let campaign = Campaign::load_default();
+    let daily = match saved {
+        Some(ref state) => state.daily,
+        None => (start == Start::Daily).then(today),
+    }
+    .map(Challenge::for_day);
+    // A saved game continues on its own grid, with its own difficulty, map and stage
+    let (mut grid_size, border_mode, difficulty, map_name, mode, stage) = match saved {
+        Some(ref state) => {
+            let difficulty = Difficulty::find(&state.difficulty, &settings);
+            let map = state.map.clone();
+            (
+                state.grid_size,
+                state.border_mode,
+                difficulty,
+                map,
+                state.mode,
+                state.stage,
+            )
+        }
+        None => {
+            // The daily challenge is the same for everybody, whatever the settings
+            let difficulty = match daily {
+                Some(_) => Difficulty::find(&Difficulty::default().name, &settings),
+                None => settings.difficulty(),
+            };
+            let border_mode = daily
+                .as_ref()
+                .map_or(difficulty.border_mode, |challenge| challenge.border_mode);
+            let mode = match (start, &daily) {
+                (Start::Campaign(_), _) => GameMode::Campaign,
+                (_, Some(challenge)) => challenge.mode,
+                _ => settings.mode,
+            };
+            // A campaign started from the settings begins with the first stage
+            let stage = match start {
+                Start::Campaign(stage) => Some(stage),
+                _ => (mode == GameMode::Campaign).then_some(0),
+            };
+            let map = match stage {
+                Some(stage) => campaign.stage(stage).map(|stage| stage.map.clone()),
+                None => settings.map.clone(),
+            };
+            (
+                settings.grid_size,
+                border_mode,
+                difficulty,
+                map,
+                mode,
+                stage,
+            )
+        }
+    };
+    // Maps are played on a grid of their own size, the daily challenge generates its own
+    let map = match daily {
+        Some(ref challenge) => challenge.map()?,
+        None => find_map(map_name),
+    };
+    if let (None, Some(challenge)) = (&saved, &daily) {
+        let mut results = load_daily_results();
+        if !results.start(challenge.day) {
+            println!(
+                "Game: The daily challenge of {} was played",
+                date(challenge.day)
+            );
+            return Ok(());
+        }
+        if let Err(e) = save_daily_results(&results) {
+            println!("Game: Failed to save the daily results: {}", e);
+        }
+    }
+    if !map.name.is_empty() {
+        grid_size = map.grid_size;
+    }
     let game_config = Config {
-        initial_speed: 100,
+        grid_size,
+        border_mode,
+        initial_speed: difficulty.initial_speed,
+        speed_increase_per_level: difficulty.speed_increase_per_level,
+        maximum_speed: difficulty.maximum_speed,
+        speed_curve: difficulty.speed_curve,
+        food_types: FoodTypes::load_default().with_weights(&difficulty.food_weights),
+        difficulty: difficulty.name,
         initial_size: 8,
         score_per_level: 10,
-        grid_resolution: 10,
-        ..Config::default()
+        grid_resolution: grid_resolution(grid_size),
+        starting_position: (grid_size.0 / 2, grid_size.1 / 2),
+        theme: Theme::find(&settings.theme).with_color_vision(settings.color_vision),
+        shape_markers: settings.shape_markers,
+        player_name: settings.player_name.clone(),
+        smooth_movement: settings.smooth_movement,
+        control_scheme: settings.control_scheme,
+        map,
+        mode,
+        campaign,
+        stage,
+        daily,
     };
-    let game_config = Arc::new(game_config);
+    let theme = &game_config.theme;
+
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+    let text_color: Color = theme.text.into();
+    let hud_background: Option<Color> = theme.hud_background.map(|c| c.into());
+
+    let mut game_config = Arc::new(game_config);
 
     // let window: sdl2::video::Window = video_subsystem
     //     .window(
@@ -254,11 +1287,13 @@ pub fn run(
 
     // let mut canvas: WindowCanvas = window.into_canvas().build().unwrap();
     let texture_creator = canvas.texture_creator();
+    let sprites = Sprites::load(&texture_creator, &game_config);
 
     // Initialize sound system
     let (_stream, stream_handle) =
         OutputStream::try_default().expect("Output stream failed to open");
-    let snd = SoundSystem::new(stream_handle);
+    let mut snd = SoundSystem::new(stream_handle);
+    snd.set_volume(settings.volume as f32 / 100.0);
 
     // canvas.set_draw_color(Color::RGB(0, 255, 255));
     // canvas.clear();
@@ -266,63 +1301,118 @@ pub fn run(
     let mut event_pump = sdl_context.event_pump()?;
     // let mut i = 0;
 
-    let game = Arc::new(Mutex::new(Game::new(
-        game_config.clone(),
-        Some(snd),
-        continue_game,
-    )));
-    game.lock().unwrap().setup();
+    let mut game = Game::new(game_config.clone(), Some(snd), saved);
+    game.setup();
 
-    let _timer;
-    if !game.lock().unwrap().state.game_over {
-        _timer = timer_subsystem.add_timer(0, Box::new(|| game.lock().unwrap().tick()));
-    }
+    let bindings = settings.key_bindings.clone();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone)
+        .map_err(|e| println!("Game: No game controller support: {}", e))
+        .ok();
+    let mut screenshot = false;
+    let mut pause_menu = pause_menu(&game_config);
+    let mut transition: Option<Column<PauseChoice>> = None;
+
+    let frame_duration = Duration::new(0, 1_000_000_000u32 / 60);
+    let mut timestep = FixedTimestep::new(5);
+    let mut last_frame = Instant::now();
 
     'running: loop {
-        // i = (i + 1) % 255;
-        // canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-        // canvas.clear();
+        let frame_start = Instant::now();
+        let elapsed = frame_start - last_frame;
+        last_frame = frame_start;
+
+        // Input is always handled before the ticks of the frame
         for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
-                    if !game.lock().unwrap().state.game_over {
-                        save_game_state(&game.lock().unwrap().state)
-                            .expect("Failed to save game state");
+            let mut action = match event {
+                Event::Quit { .. } => Some(Action::Quit),
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if game.paused {
+                // The overlay takes the navigation, its buttons trigger the same actions as the keys
+                action = match pause_menu.handle_event(&event, action) {
+                    Some(PauseChoice::Action(choice)) => Some(choice),
+                    // End game, the only other button of the overlay
+                    Some(_) => {
+                        game.toggle_pause();
+                        game.finish();
+                        None
+                    }
+                    None if action.is_some_and(|a| a.navigates()) => None,
+                    None => action,
+                };
+            } else if let Some(ref mut menu) = transition {
+                action = match menu.handle_event(&event, action) {
+                    Some(PauseChoice::Action(choice)) => Some(choice),
+                    // Continue, the only other button of the transition
+                    Some(_) => {
+                        game_config = advance_stage(&mut game);
+                        transition = None;
+                        timestep.reset();
+                        None
+                    }
+                    None if action.is_some_and(|a| a.navigates()) => None,
+                    None => action,
+                };
+            }
+            match action {
+                Some(Action::Quit) => {
+                    // A cleared stage is saved as the start of the next one
+                    if game.stage_cleared() {
+                        advance_stage(&mut game);
+                    }
+                    if !game.state.game_over {
+                        save_game_state(&game.state).expect("Failed to save game state");
                     }
                     break 'running;
                 }
-                _ => {
-                    if let Event::KeyDown { keycode, .. } = event {
-                        if let Some(key) = keycode {
-                            game.lock().unwrap().keypress(key);
-                        }
-                    }
+                Some(Action::Pause) => game.toggle_pause(),
+                Some(Action::Restart) => {
+                    game.restart();
+                    transition = None;
+                    timestep.reset();
                 }
+                Some(Action::Screenshot) => screenshot = true,
+                Some(action) => game.input(action),
+                None => {}
             }
         }
-        // The rest of the game loop goes here...
-        game.lock().unwrap().draw(canvas, game_config.clone());
+
+        run_ticks(&mut game, &mut timestep, elapsed);
+        if game.stage_cleared() && transition.is_none() {
+            let cleared = game.state.stage.unwrap_or(0);
+            transition = Some(stage_menu(&game_config, cleared));
+        }
+        game.alpha = timestep.alpha(game.tick_duration());
+
+        game.draw(canvas, game_config.clone(), &sprites);
 
         // render a surface, and convert it to a texture bound to the canvas
         let surface = font
-            .render(game.lock().unwrap().get_status_text().as_str())
-            .blended(Color::RGBA(255, 255, 255, 200))
+            .render(game.get_status_text().as_str())
+            .blended(text_color)
             .map_err(|e| e.to_string())?;
         let texture = texture_creator
             .create_texture_from_surface(&surface)
             .map_err(|e| e.to_string())?;
 
         let dest = Rect::new(0, 0, surface.width(), surface.height());
+        if let Some(background) = hud_background {
+            canvas.set_draw_color(background);
+            canvas.fill_rect(dest)?;
+        }
         canvas.copy(&texture, None, Some(dest))?;
 
-        if game.lock().unwrap().state.game_over {
+        if game.paused {
+            pause_menu.draw(canvas, &font, &texture_creator, &game_config.theme)?;
+        } else if let Some(ref menu) = transition {
+            menu.draw(canvas, &font, &texture_creator, &game_config.theme)?;
+        } else if game.state.game_over {
             let surface = font
-                .render("Game Over")
-                .blended(Color::RGBA(255, 255, 255, 200))
+                .render(game.end_text())
+                .blended(text_color)
                 .map_err(|e| e.to_string())?;
             let texture = texture_creator
                 .create_texture_from_surface(&surface)
@@ -332,8 +1422,20 @@ pub fn run(
             canvas.copy(&texture, None, Some(dest))?;
         }
 
+        if screenshot {
+            screenshot = false;
+            match save_screenshot(canvas) {
+                Ok(filename) => println!("Game: Screenshot saved to {}", filename),
+                Err(e) => println!("Game: Failed to save screenshot: {}", e),
+            }
+        }
+
         canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+
+        // Rendering runs at a fixed frame rate, independent of the game speed
+        if let Some(remaining) = frame_duration.checked_sub(frame_start.elapsed()) {
+            ::std::thread::sleep(remaining);
+        }
     }
 
     Ok(())
@@ -342,6 +1444,9 @@ pub fn run(
 #[cfg(test)]
 mod tests {
 
+    use snake::challenge::DAILY_GRID_SIZE;
+    use snake::hazard::Motion;
+
     use super::*;
 
     #[test]
@@ -353,45 +1458,383 @@ mod tests {
             score_per_level: 10,
             ..Config::default()
         });
-        let game = Game {
-            config: config.clone(),
-            snd: None,
-            state: GameState {
-                food: None,
-                snake: Snake::new(config.clone()),
-                score: 10,
-                level: 0,
-                speed: 100,
-                game_over: false,
-            },
+        let game = |score| {
+            let mut game = Game::new(config.clone(), None, None);
+            game.state.score = score;
+            game.state.level = 0;
+            game.state.speed = 100;
+            game
         };
-        assert_eq!(game.calculate_speed(), 90);
-        let game = Game {
-            config: config.clone(),
-            snd: None,
-            state: GameState {
-                food: None,
-                snake: Snake::new(config.clone()),
-                score: 100,
-                level: 0,
-                speed: 100,
-                game_over: false,
-            },
+        assert_eq!(game(10).calculate_speed(), 90);
+        assert_eq!(game(100).calculate_speed(), 10);
+        assert_eq!(game(1000).calculate_speed(), 10);
+    }
+
+    #[test]
+    fn test_sound_playback() {
+        let config = Arc::new(Config {
+            grid_size: (11, 10),
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        assert_eq!(game.eat_playback(0, 0).pan, -1.0);
+        assert_eq!(game.eat_playback(5, 0).pan, 0.0);
+        assert_eq!(game.eat_playback(10, 0).pan, 1.0);
+        assert_eq!(game.eat_playback(0, 0).pitch, 1.0);
+        assert!(game.eat_playback(0, 4).pitch > game.eat_playback(0, 2).pitch);
+        assert_eq!(game.eat_playback(0, 1000).pitch, 2.0);
+
+        let short = game.game_over_playback().pitch;
+        game.state.snake.grow(20);
+        for _ in 0..20 {
+            game.state.snake.update(config.clone());
+        }
+        assert!(game.game_over_playback().pitch < short);
+    }
+
+    #[test]
+    fn test_input_queue() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+
+        // Moving left, up then right before the next tick must not reverse the snake
+        game.input(Action::Up);
+        game.input(Action::Right);
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(5, 4));
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(6, 4));
+        assert!(!game.state.game_over);
+
+        // The queue is bounded
+        for _ in 0..10 {
+            game.input(Action::Up);
+            game.input(Action::Right);
+        }
+        assert_eq!(game.inputs.len(), MAX_QUEUED_INPUTS);
+    }
+
+    #[test]
+    fn test_relative_controls() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            control_scheme: ControlScheme::Relative,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+
+        // Moving left, turn right twice: up then right, absolute directions are ignored
+        game.input(Action::Right);
+        game.input(Action::Right);
+        game.input(Action::Up);
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(5, 4));
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(6, 4));
+        game.input(Action::Left);
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(6, 3));
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(6, 2));
+    }
+
+    #[test]
+    fn test_pause_and_restart() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.toggle_pause();
+        assert!(game.paused);
+        game.toggle_pause();
+        assert!(!game.paused);
+
+        game.input(Action::Up);
+        game.tick();
+        game.state.score = 42;
+        game.toggle_pause();
+        game.restart();
+        assert!(!game.paused);
+        assert_eq!(game.state.score, 0);
+        assert_eq!(game.state.snake.head(), &Block(5, 5));
+        assert!(game.state.food.is_some());
+    }
+
+    #[test]
+    fn test_unpause_without_burst() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        let mut timestep = FixedTimestep::new(5);
+        let tick = game.tick_duration();
+        assert_eq!(run_ticks(&mut game, &mut timestep, tick * 2), 2);
+
+        game.toggle_pause();
+        assert_eq!(run_ticks(&mut game, &mut timestep, tick * 10), 0);
+        game.toggle_pause();
+        assert_eq!(run_ticks(&mut game, &mut timestep, tick / 2), 0);
+        assert_eq!(run_ticks(&mut game, &mut timestep, tick / 2), 1);
+    }
+
+    #[test]
+    fn test_effect_food() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        let food = |type_: &str, x| Food {
+            type_: String::from(type_),
+            position: Block(x, 5),
+            ticks_left: None,
         };
-        assert_eq!(game.calculate_speed(), 10);
-        let game = Game {
-            config: config.clone(),
-            snd: None,
-            state: GameState {
-                food: None,
-                snake: Snake::new(config.clone()),
-                score: 1000,
-                level: 0,
-                speed: 100,
-                game_over: false,
+
+        game.state.food = Some(food("Star", 4));
+        game.tick();
+        assert_eq!(game.state.effects.score_multiplier(), 2);
+        game.state.food = Some(food("Banana", 3));
+        game.tick();
+        // Second food in a row, the combo doubles the score too
+        assert_eq!(game.state.score, 12);
+
+        game.state.food = Some(food("Pepper", 2));
+        game.tick();
+        let speed = game.state.speed as u64;
+        assert_eq!(
+            game.tick_duration(),
+            Duration::from_millis(speed * 60 / 100)
+        );
+        assert_eq!(
+            game.get_status_text(),
+            "Score: 24 Level: 2 Combo: x3 [x2 98] [Fast 60]"
+        );
+    }
+
+    #[test]
+    fn test_power_ups() {
+        let config = Arc::new(Config {
+            starting_position: (1, 5),
+            initial_size: 1,
+            border_mode: BorderMode::Walls,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = None;
+        game.state.power_up = Some(PowerUp {
+            kind: PowerUpKind::Shield,
+            position: Block(0, 5),
+            ticks_left: 10,
+        });
+        game.tick();
+        assert!(game.state.power_ups.shield);
+        assert_eq!(game.get_status_text(), "Score: 0 Level: 0 [Shield]");
+
+        // The shield absorbs the wall, and the snake wraps around
+        game.tick();
+        assert!(!game.state.game_over);
+        assert!(!game.state.power_ups.shield);
+        assert_eq!(game.state.snake.head(), &Block(config.grid_size.0 - 1, 5));
+    }
+
+    #[test]
+    fn test_magnet_blocked() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            map: Map {
+                walls: vec![Block(2, 5)],
+                ..Map::default()
             },
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.power_ups.magnet = 10;
+        // The wall is between the food and the head
+        game.state.food.as_mut().unwrap().position = Block(1, 5);
+        game.tick();
+        assert_eq!(game.state.snake.head(), &Block(4, 5));
+        assert_eq!(game.state.food.as_ref().unwrap().position, Block(1, 5));
+
+        game.state.food.as_mut().unwrap().position = Block(1, 7);
+        game.tick();
+        assert_eq!(game.state.food.as_ref().unwrap().position, Block(2, 7));
+    }
+
+    #[test]
+    fn test_hazard() {
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = None;
+        game.state.power_ups.shield = true;
+        // Slides down onto the block the snake moves to
+        game.state.hazards = vec![Hazard::new(Motion::Slide {
+            from: Block(4, 4),
+            to: Block(4, 9),
+            period: 1,
+        })];
+        game.tick();
+        assert_eq!(game.state.hazards[0].blocks(), vec![Block(4, 5)]);
+        assert!(!game.state.game_over);
+        assert!(!game.state.power_ups.shield);
+    }
+
+    #[test]
+    fn test_shrinking_arena() {
+        let config = Arc::new(Config {
+            grid_size: (10, 10),
+            starting_position: (5, 5),
+            initial_size: 1,
+            mode: GameMode::Shrinking,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = None;
+        for _ in 0..SHRINK_PERIOD {
+            game.tick();
+        }
+        // One point per tick survived, one level per ring
+        assert_eq!(game.state.score, SHRINK_PERIOD);
+        assert_eq!(game.state.level, 1);
+        assert_eq!(game.closed_rings(), 1);
+        assert_eq!(game.state.snake.head(), &Block(5, 5));
+
+        // Into the closed ring, the shield keeps the snake alive
+        game.state.power_ups.shield = true;
+        for _ in 0..5 {
+            game.tick();
+        }
+        assert_eq!(game.state.snake.head(), &Block(0, 5));
+        assert!(!game.state.game_over);
+    }
+
+    #[test]
+    fn test_zen() {
+        let config = Arc::new(Config {
+            starting_position: (0, 5),
+            initial_size: 1,
+            border_mode: BorderMode::Walls,
+            mode: GameMode::Zen,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = None;
+        game.tick();
+        assert!(!game.state.game_over);
+        game.state.score = 100;
+        assert_eq!(game.calculate_speed(), config.initial_speed);
+    }
+
+    #[test]
+    fn test_time_attack() {
+        let config = Arc::new(Config {
+            initial_speed: 100,
+            mode: GameMode::TimeAttack,
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = None;
+        assert_eq!(game.get_status_text(), "Score: 0 Level: 0 Time: 2:00");
+        for _ in 0..10 {
+            game.tick();
+        }
+        assert_eq!(game.state.elapsed, 1000);
+        assert!(game
+            .get_status_text()
+            .starts_with("Score: 0 Level: 0 Time: 1:59"));
+    }
+
+    #[test]
+    fn test_campaign_stage() {
+        let campaign: Campaign = serde_json::from_str(
+            r#"[{"map": "Missing", "goal": 1}, {"map": "Missing", "goal": 3}]"#,
+        )
+        .unwrap();
+        let config = Arc::new(Config {
+            starting_position: (5, 5),
+            initial_size: 1,
+            mode: GameMode::Campaign,
+            campaign,
+            stage: Some(0),
+            ..Config::default()
+        });
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.state.food = Some(Food {
+            type_: String::from("Banana"),
+            position: Block(4, 5),
+            ticks_left: None,
+        });
+        game.tick();
+        assert!(game.stage_cleared());
+        assert!(!game.state.game_over);
+        let score = game.state.score;
+        assert!(score > 0);
+
+        game.next_stage(Arc::new(next_stage_config(&game.config)));
+        assert!(!game.stage_cleared());
+        assert_eq!(game.state.stage, Some(1));
+        assert_eq!(game.state.score, score);
+        assert_eq!(game.state.level, 1);
+        assert!(game.state.speed < config.initial_speed);
+        assert!(game.get_status_text().contains("Stage: 2 Food: 0/3"));
+    }
+
+    #[test]
+    fn test_daily_challenge() {
+        let challenge = Challenge::for_day(20_744);
+        let config = Arc::new(Config {
+            grid_size: DAILY_GRID_SIZE,
+            starting_position: (DAILY_GRID_SIZE.0 / 2, DAILY_GRID_SIZE.1 / 2),
+            map: challenge.map().unwrap(),
+            daily: Some(challenge),
+            ..Config::default()
+        });
+        let play = || {
+            let mut game = Game::new(config.clone(), None, None);
+            game.setup();
+            let mut foods = Vec::new();
+            for _ in 0..5 {
+                // A new food every tick, as if the snake ate it
+                game.create_food();
+                foods.push(game.state.food.as_ref().unwrap().position);
+                game.state.ticks += 1;
+                game.rng = tick_rng(&game.config, game.state.ticks);
+            }
+            foods
         };
-        assert_eq!(game.calculate_speed(), 10);
+        // Same board for everybody
+        assert_eq!(play(), play());
+
+        let mut game = Game::new(config.clone(), None, None);
+        game.setup();
+        game.tick();
+        game.restart();
+        assert_eq!(game.state.ticks, 1);
+        assert_eq!(game.state.daily, Some(20_744));
     }
 
     // #[test]
@@ -0,0 +1,252 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::{GameControllerSubsystem, Sdl};
+
+use crate::input::Action;
+
+/// Default analog stick deadzone, out of 32767
+pub const DEFAULT_DEADZONE: i16 = 8000;
+
+///
+/// Turn game controller events into actions
+///
+/// The d-pad and the buttons trigger an action when pressed, the left stick
+/// triggers a direction when it leaves the deadzone or changes direction.
+///
+#[derive(Debug)]
+pub struct GamepadMapper {
+    deadzone: i16,
+    stick: (i16, i16),            // Left stick position
+    stick_action: Option<Action>, // Direction the stick is pushed to
+}
+
+impl GamepadMapper {
+    pub fn new(deadzone: i16) -> GamepadMapper {
+        GamepadMapper {
+            deadzone,
+            stick: (0, 0),
+            stick_action: None,
+        }
+    }
+
+    pub fn button_action(button: Button) -> Option<Action> {
+        match button {
+            Button::DPadUp => Some(Action::Up),
+            Button::DPadDown => Some(Action::Down),
+            Button::DPadLeft => Some(Action::Left),
+            Button::DPadRight => Some(Action::Right),
+            Button::Start => Some(Action::Pause),
+            Button::Back => Some(Action::Quit),
+            // Pause rather than quit, an accidental press must not end the game
+            Button::B => Some(Action::Pause),
+            Button::A => Some(Action::Confirm),
+            Button::Y => Some(Action::Restart),
+            _ => None,
+        }
+    }
+
+    /// Direction of the stick, None inside the deadzone
+    fn stick_direction(&self) -> Option<Action> {
+        let (x, y) = (self.stick.0 as i32, self.stick.1 as i32);
+        if x.abs().max(y.abs()) < self.deadzone as i32 {
+            return None;
+        }
+        // The axis pushed the most wins, y grows downwards
+        if x.abs() > y.abs() {
+            Some(if x > 0 { Action::Right } else { Action::Left })
+        } else {
+            Some(if y > 0 { Action::Down } else { Action::Up })
+        }
+    }
+
+    pub fn map(&mut self, event: &Event) -> Option<Action> {
+        match event {
+            Event::ControllerButtonDown { button, .. } => GamepadMapper::button_action(*button),
+            Event::ControllerAxisMotion { axis, value, .. } => {
+                match axis {
+                    Axis::LeftX => self.stick.0 = *value,
+                    Axis::LeftY => self.stick.1 = *value,
+                    _ => return None,
+                }
+                let direction = self.stick_direction();
+                if direction == self.stick_action {
+                    return None;
+                }
+                self.stick_action = direction;
+                direction
+            }
+            _ => None,
+        }
+    }
+}
+
+///
+/// Connected game controllers
+///
+/// Controllers are opened when plugged and closed when unplugged, every
+/// controller drives the same actions as the keyboard.
+///
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    controllers: HashMap<u32, GameController>, // Open controllers by instance id
+    mapper: GamepadMapper,
+}
+
+impl Gamepads {
+    pub fn new(sdl_context: &Sdl, deadzone: i16) -> Result<Gamepads, String> {
+        let subsystem = sdl_context.game_controller()?;
+        let mut gamepads = Gamepads {
+            subsystem,
+            controllers: HashMap::new(),
+            mapper: GamepadMapper::new(deadzone),
+        };
+        // Controllers plugged before the game started
+        for index in 0..gamepads.subsystem.num_joysticks()? {
+            gamepads.open(index);
+        }
+        Ok(gamepads)
+    }
+
+    fn open(&mut self, index: u32) {
+        if !self.subsystem.is_game_controller(index) {
+            return;
+        }
+        match self.subsystem.open(index) {
+            Ok(controller) => {
+                if let Entry::Vacant(entry) = self.controllers.entry(controller.instance_id()) {
+                    println!("Gamepads: Connected {}", controller.name());
+                    entry.insert(controller);
+                }
+            }
+            Err(e) => println!("Gamepads: Failed to open controller {}: {}", index, e),
+        }
+    }
+
+    /// Number of connected controllers
+    pub fn connected(&self) -> usize {
+        self.controllers.len()
+    }
+
+    ///
+    /// Handle hot-plug events, returns the action triggered by the event
+    ///
+    pub fn handle_event(&mut self, event: &Event) -> Option<Action> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                self.open(*which);
+                None
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.controllers.remove(which) {
+                    println!("Gamepads: Disconnected {}", controller.name());
+                }
+                None
+            }
+            _ => self.mapper.map(event),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn axis(axis: Axis, value: i16) -> Event {
+        Event::ControllerAxisMotion {
+            timestamp: 0,
+            which: 0,
+            axis,
+            value,
+        }
+    }
+
+    fn button(button: Button) -> Event {
+        Event::ControllerButtonDown {
+            timestamp: 0,
+            which: 0,
+            button,
+        }
+    }
+
+    #[test]
+    fn test_buttons() {
+        let mut mapper = GamepadMapper::new(DEFAULT_DEADZONE);
+        assert_eq!(mapper.map(&button(Button::DPadUp)), Some(Action::Up));
+        assert_eq!(mapper.map(&button(Button::DPadLeft)), Some(Action::Left));
+        assert_eq!(mapper.map(&button(Button::Start)), Some(Action::Pause));
+        assert_eq!(mapper.map(&button(Button::A)), Some(Action::Confirm));
+        assert_eq!(mapper.map(&button(Button::B)), Some(Action::Pause));
+        assert_eq!(mapper.map(&button(Button::Back)), Some(Action::Quit));
+        assert_eq!(mapper.map(&button(Button::LeftShoulder)), None);
+        let release = Event::ControllerButtonUp {
+            timestamp: 0,
+            which: 0,
+            button: Button::DPadUp,
+        };
+        assert_eq!(mapper.map(&release), None);
+    }
+
+    #[test]
+    fn test_stick_deadzone() {
+        let mut mapper = GamepadMapper::new(DEFAULT_DEADZONE);
+        assert_eq!(mapper.map(&axis(Axis::LeftX, 5000)), None);
+        assert_eq!(mapper.map(&axis(Axis::LeftX, 20000)), Some(Action::Right));
+        // Holding the stick doesn't repeat the action
+        assert_eq!(mapper.map(&axis(Axis::LeftX, 25000)), None);
+        assert_eq!(mapper.map(&axis(Axis::LeftY, -30000)), Some(Action::Up));
+        assert_eq!(mapper.map(&axis(Axis::LeftY, 0)), Some(Action::Right));
+        assert_eq!(mapper.map(&axis(Axis::LeftX, 0)), None);
+        // Back in the deadzone, the same direction triggers again
+        assert_eq!(mapper.map(&axis(Axis::LeftX, 20000)), Some(Action::Right));
+        assert_eq!(mapper.map(&axis(Axis::RightX, -30000)), None);
+    }
+
+    #[test]
+    #[ignore = "needs the SDL2 library, version 2.0.14 or later"]
+    fn test_virtual_controller() {
+        use sdl2::sys;
+
+        let sdl_context = sdl2::init().unwrap();
+        let mut event_pump = sdl_context.event_pump().unwrap();
+        let mut gamepads = Gamepads::new(&sdl_context, DEFAULT_DEADZONE).unwrap();
+        let mut poll = |gamepads: &mut Gamepads| -> Vec<Action> {
+            event_pump
+                .poll_iter()
+                .filter_map(|event| gamepads.handle_event(&event))
+                .collect()
+        };
+
+        let index = unsafe {
+            sys::SDL_JoystickAttachVirtual(
+                sys::SDL_JoystickType::SDL_JOYSTICK_TYPE_GAMECONTROLLER,
+                sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as i32,
+                sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_MAX as i32,
+                0,
+            )
+        };
+        assert!(index >= 0, "{}", sdl2::get_error());
+        let joystick = unsafe { sys::SDL_JoystickOpen(index) };
+        assert!(!joystick.is_null(), "{}", sdl2::get_error());
+        poll(&mut gamepads);
+        assert_eq!(gamepads.connected(), 1);
+
+        let dpad_up = sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_DPAD_UP as i32;
+        unsafe { sys::SDL_JoystickSetVirtualButton(joystick, dpad_up, 1) };
+        assert_eq!(poll(&mut gamepads), vec![Action::Up]);
+        unsafe { sys::SDL_JoystickSetVirtualButton(joystick, dpad_up, 0) };
+        let left_x = sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX as i32;
+        unsafe { sys::SDL_JoystickSetVirtualAxis(joystick, left_x, -32000) };
+        assert_eq!(poll(&mut gamepads), vec![Action::Left]);
+
+        unsafe {
+            sys::SDL_JoystickClose(joystick);
+            sys::SDL_JoystickDetachVirtual(index);
+        }
+        poll(&mut gamepads);
+        assert_eq!(gamepads.connected(), 0);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::entity::Block;
+use crate::map::Map;
+
+/// Highest density of obstacles, in percent of the grid
+pub const MAX_DENSITY: u32 = 40;
+
+/// Smallest grid a map can be generated on
+pub const MIN_GRID_SIZE: (u32, u32) = (5, 5);
+
+///
+/// Kind of map the generator makes
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Maze,                       // Corridors one block wide, surrounded by walls
+    Obstacles { density: u32 }, // Walls scattered anywhere, density in percent
+    Symmetric { density: u32 }, // Obstacles mirrored into the four quarters of the grid
+}
+
+impl Layout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Maze => "Maze",
+            Layout::Obstacles { .. } => "Obstacles",
+            Layout::Symmetric { .. } => "Symmetric",
+        }
+    }
+}
+
+///
+/// Generate a map from a seed, the same seed always gives the same map
+///
+/// The row the snake starts on is left open, and the blocks the snake
+/// can't reach from its start are walled up, so food never spawns out of reach
+///
+pub fn generate(layout: Layout, grid_size: (u32, u32), seed: u64) -> Result<Map, String> {
+    if grid_size.0 < MIN_GRID_SIZE.0 || grid_size.1 < MIN_GRID_SIZE.1 {
+        return Err(format!(
+            "Grid {} x {} is too small to generate a map",
+            grid_size.0, grid_size.1
+        ));
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, height) = (grid_size.0 as usize, grid_size.1 as usize);
+    let mut walls = match layout {
+        Layout::Maze => maze(&mut rng, width, height),
+        Layout::Obstacles { density } => {
+            check_density(density)?;
+            let mut walls = vec![vec![false; width]; height];
+            for row in walls.iter_mut() {
+                for wall in row.iter_mut() {
+                    *wall = rng.random_ratio(density, 100);
+                }
+            }
+            walls
+        }
+        Layout::Symmetric { density } => {
+            check_density(density)?;
+            let mut walls = vec![vec![false; width]; height];
+            for y in 0..height.div_ceil(2) {
+                for x in 0..width.div_ceil(2) {
+                    if rng.random_ratio(density, 100) {
+                        let (mx, my) = (width - 1 - x, height - 1 - y);
+                        for (x, y) in [(x, y), (mx, y), (x, my), (mx, my)] {
+                            walls[y][x] = true;
+                        }
+                    }
+                }
+            }
+            walls
+        }
+    };
+
+    // The starting row, and its mirror on grids of an even height
+    for y in [height / 2, height - 1 - height / 2] {
+        let open = match layout {
+            Layout::Maze => 1..width - 1,
+            _ => 0..width,
+        };
+        for x in open {
+            walls[y][x] = false;
+        }
+    }
+
+    let mut map = Map {
+        name: format!("{} {}", layout.name(), seed),
+        grid_size,
+        walls: wall_blocks(&walls),
+        ..Map::default()
+    };
+    let start = Block(grid_size.0 / 2, grid_size.1 / 2);
+    let reached = reachable(&map, start);
+    map.walls = (0..grid_size.1)
+        .flat_map(|y| (0..grid_size.0).map(move |x| Block(x, y)))
+        .filter(|block| !reached.contains(block))
+        .collect();
+    Ok(map)
+}
+
+fn check_density(density: u32) -> Result<(), String> {
+    if density > MAX_DENSITY {
+        return Err(format!(
+            "Density {}% is above the maximum of {}%",
+            density, MAX_DENSITY
+        ));
+    }
+    Ok(())
+}
+
+///
+/// Maze carved with a depth-first search, from a grid full of walls
+/// Corridors are on the odd rows and columns, every corridor leads everywhere
+///
+fn maze(rng: &mut StdRng, width: usize, height: usize) -> Vec<Vec<bool>> {
+    let mut walls = vec![vec![true; width]; height];
+    walls[1][1] = false;
+    let mut path = vec![(1, 1)];
+    while let Some(&(x, y)) = path.last() {
+        let mut next: Vec<(usize, usize)> = [(2, 0), (0, 2), (-2, 0), (0, -2)]
+            .iter()
+            .filter_map(|(dx, dy)| {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                let inside = nx >= 1 && ny >= 1 && nx < width as i32 - 1 && ny < height as i32 - 1;
+                (inside && walls[ny as usize][nx as usize]).then_some((nx as usize, ny as usize))
+            })
+            .collect();
+        next.shuffle(rng);
+        match next.first() {
+            Some(&(nx, ny)) => {
+                walls[(y + ny) / 2][(x + nx) / 2] = false;
+                walls[ny][nx] = false;
+                path.push((nx, ny));
+            }
+            None => {
+                path.pop();
+            }
+        }
+    }
+    walls
+}
+
+/// Wall blocks of a grid, row by row like in a map file
+fn wall_blocks(walls: &[Vec<bool>]) -> Vec<Block> {
+    walls
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, wall)| **wall)
+                .map(move |(x, _)| Block(x as u32, y as u32))
+        })
+        .collect()
+}
+
+///
+/// Blocks the snake can reach from a block, going around the walls and through the portals
+/// The edges of the grid are walls, a reachable block is reachable in both border modes
+///
+pub fn reachable(map: &Map, from: Block) -> HashSet<Block> {
+    let mut reached = HashSet::new();
+    if map.wall(&from) {
+        return reached;
+    }
+    let (width, height) = map.grid_size;
+    let mut queue = VecDeque::from([from]);
+    reached.insert(from);
+    while let Some(block) = queue.pop_front() {
+        let mut next = Vec::new();
+        if block.0 > 0 {
+            next.push(Block(block.0 - 1, block.1));
+        }
+        if block.1 > 0 {
+            next.push(Block(block.0, block.1 - 1));
+        }
+        if block.0 + 1 < width {
+            next.push(Block(block.0 + 1, block.1));
+        }
+        if block.1 + 1 < height {
+            next.push(Block(block.0, block.1 + 1));
+        }
+        next.extend(map.portal(&block));
+        for block in next {
+            if !map.wall(&block) && reached.insert(block) {
+                queue.push_back(block);
+            }
+        }
+    }
+    reached
+}
+
+/// Whether every block that isn't a wall can be reached from a block
+pub fn all_reachable(map: &Map, from: Block) -> bool {
+    let open = map.grid_size.0 * map.grid_size.1 - map.walls.len() as u32;
+    reachable(map, from).len() as u32 == open
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn start(map: &Map) -> Block {
+        Block(map.grid_size.0 / 2, map.grid_size.1 / 2)
+    }
+
+    #[test]
+    fn test_maze() {
+        for seed in 0..10 {
+            let map = generate(Layout::Maze, (40, 30), seed).unwrap();
+            assert!(all_reachable(&map, start(&map)), "seed {}", seed);
+            assert!(!map.wall(&start(&map)));
+            assert!(map.wall(&Block(0, 0)));
+            // Roughly half the grid is corridors
+            assert!(map.walls.len() > 400, "seed {}", seed);
+        }
+        assert!(all_reachable(
+            &generate(Layout::Maze, (5, 6), 1).unwrap(),
+            Block(2, 3)
+        ));
+    }
+
+    #[test]
+    fn test_same_seed() {
+        let layout = Layout::Obstacles { density: 20 };
+        let map = generate(layout, (40, 30), 42).unwrap();
+        assert_eq!(map, generate(layout, (40, 30), 42).unwrap());
+        assert_ne!(map, generate(layout, (40, 30), 43).unwrap());
+        assert_eq!(map.name, "Obstacles 42");
+    }
+
+    #[test]
+    fn test_obstacles() {
+        for density in [0, 10, MAX_DENSITY] {
+            for seed in 0..10 {
+                let map = generate(Layout::Obstacles { density }, (40, 30), seed).unwrap();
+                assert!(all_reachable(&map, start(&map)));
+                assert!((0..40).all(|x| !map.wall(&Block(x, 15))));
+            }
+        }
+        let empty = generate(Layout::Obstacles { density: 0 }, (40, 30), 0).unwrap();
+        assert!(empty.walls.is_empty());
+    }
+
+    #[test]
+    fn test_symmetric() {
+        for seed in 0..10 {
+            let map = generate(Layout::Symmetric { density: 30 }, (40, 30), seed).unwrap();
+            assert!(all_reachable(&map, start(&map)));
+            for wall in &map.walls {
+                assert!(map.wall(&Block(39 - wall.0, wall.1)));
+                assert!(map.wall(&Block(wall.0, 29 - wall.1)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(generate(Layout::Maze, (4, 10), 0).is_err());
+        assert!(generate(Layout::Obstacles { density: 50 }, (40, 30), 0).is_err());
+    }
+
+    #[test]
+    fn test_reachable() {
+        let map: Map = serde_json::from_value(serde_json::json!({
+            "name": "Test",
+            "rows": ["a#a.", "####", "...."]
+        }))
+        .unwrap();
+        let reached = reachable(&map, Block(0, 0));
+        assert_eq!(reached.len(), 3);
+        assert!(reached.contains(&Block(3, 0)));
+        assert!(!all_reachable(&map, Block(0, 0)));
+        assert!(reachable(&map, Block(1, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_builtin_maps() {
+        for map in Map::all() {
+            assert!(all_reachable(&map, start(&map)), "{}", map.name);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entity::Block;
+
+/// Directions of a rotating bar, clockwise from the right
+const ROTATIONS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+///
+/// Path a hazard follows, it moves by one step every `period` ticks
+///
+/// In the map file: `{ "Slide": { "from": [3, 5], "to": [3, 20], "period": 2 } }`
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Motion {
+    Slide {
+        from: Block, // One end of the path, where the block starts
+        to: Block,   // Other end, on the same row or column
+        period: u32,
+    },
+    Rotate {
+        center: Block, // Pivot of the bar
+        length: u32,   // Blocks of the bar on one side of the pivot
+        period: u32,
+    },
+}
+
+///
+/// Obstacle moving on a path, the snake dies on contact
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hazard {
+    pub motion: Motion,
+    #[serde(default)]
+    pub step: u32, // Ticks since the start of the game
+}
+
+impl Hazard {
+    pub fn new(motion: Motion) -> Hazard {
+        Hazard { motion, step: 0 }
+    }
+
+    pub fn tick(&mut self) {
+        self.step = self.step.wrapping_add(1);
+    }
+
+    /// Blocks covered by the hazard at its current step
+    pub fn blocks(&self) -> Vec<Block> {
+        match self.motion {
+            Motion::Slide { from, to, period } => {
+                let (dx, dy) = (to.0 as i32 - from.0 as i32, to.1 as i32 - from.1 as i32);
+                let length = dx.abs().max(dy.abs()) as u32;
+                if length == 0 {
+                    return vec![from];
+                }
+                // Back and forth between the two ends
+                let moves = self.step / period.max(1) % (2 * length);
+                let offset = moves.min(2 * length - moves) as i32;
+                vec![Block(
+                    (from.0 as i32 + dx.signum() * offset) as u32,
+                    (from.1 as i32 + dy.signum() * offset) as u32,
+                )]
+            }
+            Motion::Rotate {
+                center,
+                length,
+                period,
+            } => {
+                let rotation = (self.step / period.max(1)) as usize % ROTATIONS.len();
+                let (dx, dy) = ROTATIONS[rotation];
+                (-(length as i32)..=length as i32)
+                    .map(|i| {
+                        Block(
+                            (center.0 as i32 + dx * i) as u32,
+                            (center.1 as i32 + dy * i) as u32,
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    ///
+    /// Check that the hazard stays on a grid, and that a slide is straight
+    ///
+    pub fn validate(&self, grid_size: (u32, u32)) -> Result<(), String> {
+        let inside =
+            |x: i64, y: i64| x >= 0 && y >= 0 && x < grid_size.0 as i64 && y < grid_size.1 as i64;
+        match self.motion {
+            Motion::Slide { from, to, .. } => {
+                if from.0 != to.0 && from.1 != to.1 {
+                    return Err(format!("Slide from {:?} to {:?} isn't straight", from, to));
+                }
+                if !inside(from.0 as i64, from.1 as i64) || !inside(to.0 as i64, to.1 as i64) {
+                    return Err(format!("Slide from {:?} to {:?} leaves the grid", from, to));
+                }
+            }
+            Motion::Rotate { center, length, .. } => {
+                let (x, y, length) = (center.0 as i64, center.1 as i64, length as i64);
+                if !inside(x - length, y - length) || !inside(x + length, y + length) {
+                    return Err(format!("Bar around {:?} leaves the grid", center));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_slide() {
+        let mut hazard = Hazard::new(Motion::Slide {
+            from: Block(2, 5),
+            to: Block(4, 5),
+            period: 2,
+        });
+        let mut positions = Vec::new();
+        for _ in 0..10 {
+            positions.push(hazard.blocks()[0].0);
+            hazard.tick();
+        }
+        assert_eq!(positions, vec![2, 2, 3, 3, 4, 4, 3, 3, 2, 2]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut hazard = Hazard::new(Motion::Rotate {
+            center: Block(5, 5),
+            length: 1,
+            period: 1,
+        });
+        assert_eq!(hazard.blocks(), vec![Block(4, 5), Block(5, 5), Block(6, 5)]);
+        hazard.tick();
+        assert_eq!(hazard.blocks(), vec![Block(4, 4), Block(5, 5), Block(6, 6)]);
+        hazard.tick();
+        assert_eq!(hazard.blocks(), vec![Block(5, 4), Block(5, 5), Block(5, 6)]);
+        // Same blocks after half a turn, the other way around
+        for _ in 0..2 {
+            hazard.tick();
+        }
+        assert_eq!(hazard.blocks(), vec![Block(6, 5), Block(5, 5), Block(4, 5)]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let slide = |from, to| {
+            Hazard::new(Motion::Slide {
+                from,
+                to,
+                period: 1,
+            })
+        };
+        let bar = |center, length| {
+            Hazard::new(Motion::Rotate {
+                center,
+                length,
+                period: 1,
+            })
+        };
+        assert!(slide(Block(0, 0), Block(0, 9)).validate((10, 10)).is_ok());
+        assert!(slide(Block(0, 0), Block(1, 9)).validate((10, 10)).is_err());
+        assert!(slide(Block(0, 0), Block(0, 10)).validate((10, 10)).is_err());
+        assert!(bar(Block(5, 5), 4).validate((10, 10)).is_ok());
+        assert!(bar(Block(5, 5), 5).validate((10, 10)).is_err());
+    }
+}
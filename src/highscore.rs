@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mode::GameMode;
+
+const HIGH_SCORES_FILE: &str = "highscores.json";
+
+/// Number of scores kept in the table of each game mode
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Longest player name, in characters
+pub const MAX_NAME_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    #[serde(default)]
+    pub name: String, // Name of the player
+    pub score: u32,         // Score at the end of the game
+    pub level: u32,         // Level reached
+    pub difficulty: String, // Name of the difficulty the game was played with
+    #[serde(default = "default_best_combo")]
+    pub best_combo: u32, // Highest combo multiplier of the game
+    pub timestamp: u64,     // End of the game, in seconds since the Unix epoch
+    #[serde(default)]
+    pub mode: GameMode, // Game mode, each mode has its own table
+    #[serde(default)]
+    pub time_ms: u64, // Game time, in ms
+}
+
+impl HighScore {
+    /// Whether this score ranks before another one of the same mode
+    fn beats(&self, other: &HighScore) -> bool {
+        if self.mode.ranks_by_time() {
+            self.time_ms < other.time_ms
+        } else {
+            self.score > other.score
+        }
+    }
+}
+
+/// Combo of the scores recorded before combos existed
+fn default_best_combo() -> u32 {
+    1
+}
+
+///
+/// Best scores of every game mode, best first
+///
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    pub scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Table of a game mode, best first
+    pub fn table(&self, mode: GameMode) -> Vec<&HighScore> {
+        self.scores.iter().filter(|s| s.mode == mode).collect()
+    }
+
+    ///
+    /// Add a score to the table of its mode
+    /// Returns its rank starting from 0, None if it isn't good enough
+    ///
+    pub fn add(&mut self, high_score: HighScore) -> Option<usize> {
+        // Equal scores rank after the older ones
+        let table = self.table(high_score.mode);
+        let rank = table
+            .iter()
+            .position(|s| high_score.beats(s))
+            .unwrap_or(table.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        // Before the score it beats, in the list of every mode
+        let mode = high_score.mode;
+        let index = self
+            .scores
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.mode == mode)
+            .nth(rank)
+            .map_or(self.scores.len(), |(i, _)| i);
+        self.scores.insert(index, high_score);
+
+        // Drop the last score of the mode when its table is full
+        if self.table(mode).len() > MAX_HIGH_SCORES {
+            let last = self.scores.iter().rposition(|s| s.mode == mode).unwrap();
+            self.scores.remove(last);
+        }
+        Some(rank)
+    }
+}
+
+pub fn save_high_scores(high_scores: &HighScores) -> Result<(), String> {
+    println!("Saving high scores to {}", HIGH_SCORES_FILE);
+    let json = serde_json::to_string_pretty(high_scores).map_err(|e| e.to_string())?;
+    std::fs::write(HIGH_SCORES_FILE, json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+///
+/// Load the high scores, an empty table if there are none yet
+///
+pub fn load_high_scores() -> HighScores {
+    let json = match std::fs::read_to_string(HIGH_SCORES_FILE) {
+        Ok(json) => json,
+        Err(_) => return HighScores::default(),
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        println!("Invalid high scores file ({}), starting a new table", e);
+        HighScores::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn high_score(score: u32) -> HighScore {
+        HighScore {
+            name: String::from("Player"),
+            score,
+            level: score / 10,
+            difficulty: String::from("Normal"),
+            best_combo: 1,
+            timestamp: 0,
+            mode: GameMode::Classic,
+            time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_add() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.add(high_score(10)), Some(0));
+        assert_eq!(high_scores.add(high_score(30)), Some(0));
+        assert_eq!(high_scores.add(high_score(10)), Some(2));
+        assert_eq!(high_scores.add(high_score(20)), Some(1));
+        let scores: Vec<u32> = high_scores.scores.iter().map(|s| s.score).collect();
+        assert_eq!(scores, vec![30, 20, 10, 10]);
+    }
+
+    #[test]
+    fn test_tables() {
+        let mut high_scores = HighScores::default();
+        let race = |time_ms| HighScore {
+            mode: GameMode::Race,
+            time_ms,
+            ..high_score(0)
+        };
+        high_scores.add(high_score(10));
+        assert_eq!(high_scores.add(race(40_000)), Some(0));
+        assert_eq!(high_scores.add(race(30_000)), Some(0));
+        assert_eq!(high_scores.add(race(50_000)), Some(2));
+        assert_eq!(high_scores.add(high_score(5)), Some(1));
+        for _ in 0..MAX_HIGH_SCORES {
+            high_scores.add(race(10_000));
+        }
+        // A full race table leaves the other tables alone
+        let times: Vec<u64> = high_scores
+            .table(GameMode::Race)
+            .iter()
+            .map(|s| s.time_ms)
+            .collect();
+        assert_eq!(times, vec![10_000; MAX_HIGH_SCORES]);
+        assert_eq!(high_scores.table(GameMode::Classic).len(), 2);
+        assert_eq!(high_scores.table(GameMode::Zen).len(), 0);
+    }
+
+    #[test]
+    fn test_old_high_score() {
+        let json = r#"{"score": 12, "level": 1, "difficulty": "Easy", "timestamp": 0}"#;
+        let high_score: HighScore = serde_json::from_str(json).unwrap();
+        assert_eq!(high_score.best_combo, 1);
+        assert_eq!(high_score.mode, GameMode::Classic);
+        assert_eq!(high_score.name, "");
+    }
+
+    #[test]
+    fn test_full_table() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=MAX_HIGH_SCORES as u32 {
+            high_scores.add(high_score(score * 10));
+        }
+        assert_eq!(high_scores.add(high_score(10)), None);
+        assert_eq!(high_scores.add(high_score(15)), Some(MAX_HIGH_SCORES - 1));
+        assert_eq!(high_scores.scores.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.scores.last().unwrap().score, 15);
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+use snake::gamepad::Gamepads;
+use snake::highscore::{load_high_scores, HighScores, MAX_NAME_LEN};
+use snake::input::Action;
+use snake::mode::{format_time, GameMode};
+use snake::settings::{load_settings, save_settings, Settings};
+use snake::theme::Theme;
+
+use crate::ui::Column;
+
+#[derive(Debug, Clone, PartialEq)]
+enum HighScoresChoice {
+    Name(String),
+    Mode(usize),
+    Back,
+}
+
+///
+/// Table of a game mode, races show their time instead of the score
+/// The name entered is the one recorded with the next high scores
+///
+fn widgets(high_scores: &HighScores, mode: usize, settings: &Settings) -> Column<HighScoresChoice> {
+    let modes = GameMode::all()
+        .iter()
+        .map(|m| m.label().to_string())
+        .collect();
+    let mut ui = Column::new(100, 20, 600, 32)
+        .label("High scores")
+        .text_input("Name", &settings.player_name, MAX_NAME_LEN, |name| {
+            HighScoresChoice::Name(name.to_string())
+        })
+        .list("Mode", modes, mode, HighScoresChoice::Mode);
+    let mode = GameMode::all()[mode];
+    let table = high_scores.table(mode);
+    for (rank, high_score) in table.iter().enumerate() {
+        let result = if mode.ranks_by_time() {
+            format_time(high_score.time_ms)
+        } else {
+            high_score.score.to_string()
+        };
+        ui = ui.label(&format!(
+            "{:>2}. {:<12} {:>6}  Level {:<3} Combo x{:<2} {}",
+            rank + 1,
+            high_score.name,
+            result,
+            high_score.level,
+            high_score.best_combo,
+            high_score.difficulty
+        ));
+    }
+    if table.is_empty() {
+        ui = ui.label("No scores yet");
+    }
+    ui.button("Back", HighScoresChoice::Back)
+}
+
+///
+/// High scores screen
+///
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), String> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let mut settings = load_settings();
+    let theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone).ok();
+    let text_input = sdl_context.video()?.text_input();
+
+    let high_scores = load_high_scores();
+    // Start on the table of the mode of new games
+    let mut mode = GameMode::all()
+        .iter()
+        .position(|m| *m == settings.mode)
+        .unwrap_or(0);
+    let mut ui = widgets(&high_scores, mode, &settings);
+    // On the mode rather than the name, so the keys navigate right away
+    ui.set_focus(Some(2));
+
+    loop {
+        // Only receive typed text while the name is edited
+        if ui.editing() && !text_input.is_active() {
+            text_input.start();
+        } else if !ui.editing() && text_input.is_active() {
+            text_input.stop();
+        }
+
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
+
+        for event in event_pump.poll_iter() {
+            let action = match event {
+                Event::Quit { .. } => {
+                    text_input.stop();
+                    return Ok(());
+                }
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => settings.key_bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            // Keys typed in the name are not actions
+            if action == Some(Action::Quit) && !ui.editing() {
+                text_input.stop();
+                return Ok(());
+            }
+            match ui.handle_event(&event, action) {
+                Some(HighScoresChoice::Name(name)) => {
+                    settings.player_name = name;
+                    save_settings(&settings)?;
+                }
+                Some(HighScoresChoice::Mode(index)) => {
+                    mode = index;
+                    let focus = ui.focus();
+                    ui = widgets(&high_scores, mode, &settings);
+                    ui.set_focus(focus);
+                }
+                Some(HighScoresChoice::Back) => {
+                    text_input.stop();
+                    return Ok(());
+                }
+                None => {}
+            }
+        }
+
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+
+///
+/// Something the player can do, keys are bound to actions
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,  // Also turns left with the relative control scheme
+    Right, // Also turns right with the relative control scheme
+    Pause,
+    Quit,
+    Restart,
+    Screenshot,
+    Confirm, // Activate the selected menu entry
+}
+impl Action {
+    pub fn all() -> [Action; 9] {
+        [
+            Action::Up,
+            Action::Down,
+            Action::Left,
+            Action::Right,
+            Action::Pause,
+            Action::Quit,
+            Action::Restart,
+            Action::Screenshot,
+            Action::Confirm,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Pause => "Pause",
+            Action::Quit => "Quit",
+            Action::Restart => "Restart",
+            Action::Screenshot => "Screenshot",
+            Action::Confirm => "Confirm",
+        }
+    }
+
+    /// Actions used to move around menus
+    pub fn navigates(&self) -> bool {
+        matches!(
+            self,
+            Action::Up | Action::Down | Action::Left | Action::Right | Action::Confirm
+        )
+    }
+}
+
+///
+/// Keys bound to every action
+///
+/// Saved in the settings with the SDL key names:
+/// `{ "Up": ["Up", "W"], "Quit": ["Escape"] }`
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    try_from = "HashMap<Action, Vec<String>>",
+    into = "HashMap<Action, Vec<String>>"
+)]
+pub struct KeyBindings {
+    keys: HashMap<Action, Vec<Keycode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings {
+            keys: HashMap::from([
+                (Action::Up, vec![Keycode::Up, Keycode::W]),
+                (Action::Down, vec![Keycode::Down, Keycode::S]),
+                (Action::Left, vec![Keycode::Left, Keycode::A]),
+                (Action::Right, vec![Keycode::Right, Keycode::D]),
+                (Action::Pause, vec![Keycode::P, Keycode::Space]),
+                (Action::Quit, vec![Keycode::Escape]),
+                (Action::Restart, vec![Keycode::R]),
+                (Action::Screenshot, vec![Keycode::F12]),
+                (Action::Confirm, vec![Keycode::Return, Keycode::KpEnter]),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Action bound to a key
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        Action::all()
+            .into_iter()
+            .find(|action| self.keys(*action).contains(&key))
+    }
+
+    /// Keys bound to an action
+    pub fn keys(&self, action: Action) -> &[Keycode] {
+        self.keys.get(&action).map(|k| k.as_slice()).unwrap_or(&[])
+    }
+
+    ///
+    /// Bind a key to an action, replacing its previous keys
+    /// Fails with the other action if the key is already used
+    ///
+    pub fn bind(&mut self, action: Action, key: Keycode) -> Result<(), Action> {
+        match self.action(key) {
+            Some(other) if other != action => Err(other),
+            _ => {
+                self.keys.insert(action, vec![key]);
+                Ok(())
+            }
+        }
+    }
+
+    /// Bind one more key to an action
+    pub fn add(&mut self, action: Action, key: Keycode) -> Result<(), Action> {
+        match self.action(key) {
+            Some(other) if other != action => Err(other),
+            Some(_) => Ok(()),
+            None => {
+                self.keys.entry(action).or_default().push(key);
+                Ok(())
+            }
+        }
+    }
+
+    ///
+    /// Keys bound to more than one action
+    ///
+    pub fn conflicts(&self) -> Vec<(Keycode, Vec<Action>)> {
+        let mut conflicts: Vec<(Keycode, Vec<Action>)> = Vec::new();
+        for action in Action::all() {
+            for key in self.keys(action) {
+                match conflicts.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, actions)) => actions.push(action),
+                    None => conflicts.push((*key, vec![action])),
+                }
+            }
+        }
+        conflicts.retain(|(_, actions)| actions.len() > 1);
+        conflicts
+    }
+
+    /// Actions without any key
+    pub fn unbound(&self) -> Vec<Action> {
+        Action::all()
+            .into_iter()
+            .filter(|action| self.keys(*action).is_empty())
+            .collect()
+    }
+}
+
+impl TryFrom<HashMap<Action, Vec<String>>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(names: HashMap<Action, Vec<String>>) -> Result<Self, Self::Error> {
+        // Actions missing from the file keep their default keys
+        let mut bindings = KeyBindings::default();
+        for (action, names) in names {
+            let keys = names
+                .iter()
+                .map(|name| Keycode::from_name(name).ok_or(format!("Unknown key {}", name)))
+                .collect::<Result<Vec<Keycode>, String>>()?;
+            bindings.keys.insert(action, keys);
+        }
+        if let Some((key, actions)) = bindings.conflicts().first() {
+            return Err(format!("Key {} is bound to {:?}", key.name(), actions));
+        }
+        Ok(bindings)
+    }
+}
+impl From<KeyBindings> for HashMap<Action, Vec<String>> {
+    fn from(bindings: KeyBindings) -> HashMap<Action, Vec<String>> {
+        bindings
+            .keys
+            .into_iter()
+            .map(|(action, keys)| (action, keys.into_iter().map(|k| k.name()).collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action(Keycode::W), Some(Action::Up));
+        assert_eq!(bindings.action(Keycode::Escape), Some(Action::Quit));
+        assert_eq!(bindings.action(Keycode::Z), None);
+        assert!(bindings.conflicts().is_empty());
+        assert!(bindings.unbound().is_empty());
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.bind(Action::Up, Keycode::I), Ok(()));
+        assert_eq!(bindings.keys(Action::Up), &[Keycode::I]);
+        assert_eq!(bindings.action(Keycode::W), None);
+        // Already used by another action
+        assert_eq!(bindings.bind(Action::Up, Keycode::A), Err(Action::Left));
+        assert_eq!(bindings.add(Action::Up, Keycode::S), Err(Action::Down));
+        assert_eq!(bindings.add(Action::Up, Keycode::W), Ok(()));
+        assert_eq!(bindings.keys(Action::Up), &[Keycode::I, Keycode::W]);
+        assert!(bindings.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut bindings = KeyBindings::default();
+        bindings.keys.insert(Action::Pause, vec![Keycode::Escape]);
+        assert_eq!(
+            bindings.conflicts(),
+            vec![(Keycode::Escape, vec![Action::Pause, Action::Quit])]
+        );
+        assert_eq!(bindings.unbound(), vec![]);
+        bindings.keys.insert(Action::Restart, vec![]);
+        assert_eq!(bindings.unbound(), vec![Action::Restart]);
+    }
+
+    #[test]
+    fn test_serialize_bindings() {
+        let json = serde_json::to_string(&KeyBindings::default()).unwrap();
+        let bindings: KeyBindings = serde_json::from_str(&json).unwrap();
+        assert_eq!(bindings, KeyBindings::default());
+
+        let bindings: KeyBindings = serde_json::from_str(r#"{"Up": ["I"]}"#).unwrap();
+        assert_eq!(bindings.keys(Action::Up), &[Keycode::I]);
+        assert_eq!(bindings.keys(Action::Down), &[Keycode::Down, Keycode::S]);
+
+        assert!(serde_json::from_str::<KeyBindings>(r#"{"Up": ["Nope"]}"#).is_err());
+        assert!(serde_json::from_str::<KeyBindings>(r#"{"Up": ["Escape"]}"#).is_err());
+    }
+}
@@ -1,3 +1,21 @@
-pub mod sound;
+pub mod campaign;
+pub mod challenge;
+pub mod combo;
+pub mod difficulty;
+pub mod effect;
 pub mod entity;
-pub mod savegame;
\ No newline at end of file
+pub mod food;
+pub mod gamepad;
+pub mod generator;
+pub mod hazard;
+pub mod highscore;
+pub mod input;
+pub mod map;
+pub mod mode;
+pub mod powerup;
+pub mod savegame;
+pub mod settings;
+pub mod sound;
+pub mod synth;
+pub mod theme;
+pub mod timestep;
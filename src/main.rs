@@ -1,46 +1,88 @@
+use game::Start;
 use menu::MenuChoice;
 use sdl2::render::WindowCanvas;
+use snake::settings::load_settings;
 
+mod controls;
+mod daily;
 mod game;
+mod highscores;
 mod menu;
+mod options;
+mod stages;
+mod ui;
+
+/// Size of the window, the game is scaled to it in fullscreen
+pub const WINDOW_SIZE: (u32, u32) = (800, 600);
 
 enum ScreenState {
     Menu,
     Game,
+    Campaign,
+    Daily,
+    Settings,
+    HighScores,
 }
 
-
 ///
 /// TODO: multiple food in the map
-/// TODO: Food timeout
 /// TODO: Add walls to the game (map?)
 /// TODO: Handle collision with the walls
-/// TODO: Pause screen
-/// TODO: Restart game
-/// TODO: Save high score
 ///
-
 fn main() -> Result<(), String> {
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
     let window: sdl2::video::Window = video_subsystem
-        .window("Snake game", 800, 600)
+        .window("Snake game", WINDOW_SIZE.0, WINDOW_SIZE.1)
         .position_centered()
         .build()
         .map_err(|e| e.to_string())?;
 
     let mut canvas: WindowCanvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    canvas
+        .set_logical_size(WINDOW_SIZE.0, WINDOW_SIZE.1)
+        .map_err(|e| e.to_string())?;
+    options::set_fullscreen(&mut canvas, load_settings().fullscreen)?;
 
     // start on the menu screen
     let mut screen = ScreenState::Menu;
-    let mut continue_game = false;
+    let mut start = Start::New;
 
     loop {
         let choice = match screen {
             ScreenState::Menu => Some(menu::run(&sdl_context, &mut canvas)?),
             ScreenState::Game => {
-                game::run(&sdl_context, &mut canvas, continue_game)?;
+                game::run(&sdl_context, &mut canvas, start)?;
+                screen = ScreenState::Menu;
+                None
+            }
+            ScreenState::Campaign => {
+                screen = match stages::run(&sdl_context, &mut canvas)? {
+                    Some(stage) => {
+                        start = Start::Campaign(stage);
+                        ScreenState::Game
+                    }
+                    None => ScreenState::Menu,
+                };
+                None
+            }
+            ScreenState::Daily => {
+                screen = if daily::run(&sdl_context, &mut canvas)? {
+                    start = Start::Daily;
+                    ScreenState::Game
+                } else {
+                    ScreenState::Menu
+                };
+                None
+            }
+            ScreenState::HighScores => {
+                highscores::run(&sdl_context, &mut canvas)?;
+                screen = ScreenState::Menu;
+                None
+            }
+            ScreenState::Settings => {
+                options::run(&sdl_context, &mut canvas)?;
                 screen = ScreenState::Menu;
                 None
             }
@@ -49,11 +91,23 @@ fn main() -> Result<(), String> {
         match choice {
             Some(MenuChoice::NewGame) => {
                 screen = ScreenState::Game;
-                continue_game = false;
+                start = Start::New;
             }
             Some(MenuChoice::Continue) => {
                 screen = ScreenState::Game;
-                continue_game = true;
+                start = Start::Continue;
+            }
+            Some(MenuChoice::Campaign) => {
+                screen = ScreenState::Campaign;
+            }
+            Some(MenuChoice::Daily) => {
+                screen = ScreenState::Daily;
+            }
+            Some(MenuChoice::HighScores) => {
+                screen = ScreenState::HighScores;
+            }
+            Some(MenuChoice::Settings) => {
+                screen = ScreenState::Settings;
             }
             Some(MenuChoice::Exit) => {
                 break;
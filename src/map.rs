@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::Block;
+use crate::hazard::Hazard;
+
+const MAPS_DIR: &str = "resources/maps";
+
+///
+/// Map file, the layout is drawn with one character per block:
+/// - `.` empty
+/// - `#` wall
+/// - a letter: portal, the two blocks with the same letter lead to each other
+///
+/// Moving hazards are listed apart, see `Motion` for their paths
+///
+/// `{ "name": "Box", "rows": ["#####", "#a.a#", "#####"], "hazards": [] }`
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MapFile {
+    name: String,
+    rows: Vec<String>,
+    #[serde(default)]
+    hazards: Vec<Hazard>,
+}
+
+///
+/// Walls, portals and hazards on a grid of its own size
+/// The middle of the map is where the snake starts, it should be left empty
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "MapFile", into = "MapFile")]
+pub struct Map {
+    pub name: String,                 // Shown in the settings, kept in the saves
+    pub grid_size: (u32, u32),        // Size of the layout, in blocks
+    pub walls: Vec<Block>,            // Blocks that kill the snake
+    pub portals: Vec<(Block, Block)>, // Pairs of blocks leading to each other
+    pub hazards: Vec<Hazard>,         // Moving hazards, at the start of a game
+}
+
+impl TryFrom<MapFile> for Map {
+    type Error = String;
+
+    fn try_from(file: MapFile) -> Result<Self, Self::Error> {
+        let width = file.rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(format!("Map {} is empty", file.name));
+        }
+        let mut walls = Vec::new();
+        let mut ends: HashMap<char, Vec<Block>> = HashMap::new();
+        for (y, row) in file.rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("Map {}: row {} has the wrong width", file.name, y));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let block = Block(x as u32, y as u32);
+                match c {
+                    '.' => {}
+                    '#' => walls.push(block),
+                    c if c.is_ascii_alphabetic() => ends.entry(c).or_default().push(block),
+                    c => return Err(format!("Map {}: unknown block {:?}", file.name, c)),
+                }
+            }
+        }
+        let mut letters: Vec<char> = ends.keys().copied().collect();
+        letters.sort();
+        let mut portals = Vec::new();
+        for letter in letters {
+            match ends[&letter][..] {
+                [a, b] => portals.push((a, b)),
+                _ => {
+                    return Err(format!(
+                        "Map {}: portal {} needs exactly two ends",
+                        file.name, letter
+                    ))
+                }
+            }
+        }
+        let grid_size = (width as u32, file.rows.len() as u32);
+        for hazard in &file.hazards {
+            hazard
+                .validate(grid_size)
+                .map_err(|e| format!("Map {}: {}", file.name, e))?;
+        }
+        Ok(Map {
+            name: file.name,
+            grid_size,
+            walls,
+            portals,
+            hazards: file.hazards,
+        })
+    }
+}
+
+impl From<Map> for MapFile {
+    fn from(map: Map) -> Self {
+        let (width, height) = (map.grid_size.0 as usize, map.grid_size.1 as usize);
+        let mut rows = vec![vec!['.'; width]; height];
+        for wall in &map.walls {
+            rows[wall.1 as usize][wall.0 as usize] = '#';
+        }
+        for ((a, b), letter) in map.portals.iter().zip(('a'..='z').chain('A'..='Z')) {
+            rows[a.1 as usize][a.0 as usize] = letter;
+            rows[b.1 as usize][b.0 as usize] = letter;
+        }
+        MapFile {
+            name: map.name,
+            rows: rows
+                .into_iter()
+                .map(|row| row.into_iter().collect())
+                .collect(),
+            hazards: map.hazards,
+        }
+    }
+}
+
+impl Map {
+    pub fn load(filename: &Path) -> Result<Map, String> {
+        let json = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", filename.display(), e))
+    }
+
+    ///
+    /// Every map of the maps directory, sorted by name
+    ///
+    pub fn all() -> Vec<Map> {
+        let mut maps: Vec<Map> = std::fs::read_dir(MAPS_DIR)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|path| {
+                        Map::load(&path)
+                            .map_err(|e| println!("Map: Failed to load {}", e))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        maps.sort_by(|a, b| a.name.cmp(&b.name));
+        maps
+    }
+
+    /// Find a map by name
+    pub fn find(name: &str) -> Option<Map> {
+        Map::all().into_iter().find(|map| map.name == name)
+    }
+
+    pub fn wall(&self, block: &Block) -> bool {
+        self.walls.contains(block)
+    }
+
+    /// Other end of the portal on a block, if there is one
+    pub fn portal(&self, block: &Block) -> Option<Block> {
+        self.portals.iter().find_map(|(a, b)| {
+            if a == block {
+                Some(*b)
+            } else if b == block {
+                Some(*a)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether nothing else can be put on a block
+    pub fn blocked(&self, block: &Block) -> bool {
+        self.wall(block) || self.portal(block).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn map(rows: &[&str]) -> Result<Map, String> {
+        Map::try_from(MapFile {
+            name: String::from("Test"),
+            rows: rows.iter().map(|row| row.to_string()).collect(),
+            hazards: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        let map = map(&["#####", "#a.b#", "#b.a#"]).unwrap();
+        assert_eq!(map.grid_size, (5, 3));
+        assert_eq!(map.walls.len(), 9);
+        assert!(map.wall(&Block(0, 1)));
+        assert_eq!(map.portal(&Block(1, 1)), Some(Block(3, 2)));
+        assert_eq!(map.portal(&Block(3, 2)), Some(Block(1, 1)));
+        assert_eq!(map.portal(&Block(3, 1)), Some(Block(1, 2)));
+        assert_eq!(map.portal(&Block(2, 1)), None);
+        assert!(!map.blocked(&Block(2, 2)));
+    }
+
+    #[test]
+    fn test_save() {
+        let map = map(&["#####", "#a.b#", "#b.a#"]).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+        assert!(json.contains(r##""#a.b#""##));
+        assert_eq!(serde_json::from_str::<Map>(&json).unwrap(), map);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(map(&[]).is_err());
+        assert!(map(&["...", ".."]).is_err());
+        assert!(map(&["..?"]).is_err());
+        assert!(map(&["a.."]).is_err());
+        assert!(map(&["aaa"]).is_err());
+    }
+
+    #[test]
+    fn test_builtin_maps() {
+        let maps = Map::all();
+        assert!(!maps.is_empty());
+        for map in &maps {
+            let center = Block(map.grid_size.0 / 2, map.grid_size.1 / 2);
+            assert!(!map.blocked(&center), "{}", map.name);
+            // The hazards never go through the starting row
+            for hazard in &map.hazards {
+                let mut hazard = hazard.clone();
+                for _ in 0..100 {
+                    assert!(
+                        hazard.blocks().iter().all(|b| b.1 != center.1),
+                        "{}",
+                        map.name
+                    );
+                    hazard.tick();
+                }
+            }
+        }
+    }
+}
@@ -1,112 +1,73 @@
 use std::time::Duration;
 
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::render::WindowCanvas;
 use sdl2::Sdl;
-use sdl2::{rect::Rect, render::WindowCanvas};
+use snake::gamepad::Gamepads;
+use snake::input::Action;
 use snake::savegame::savegame_exists;
+use snake::settings::load_settings;
+use snake::theme::Theme;
 
-struct MenuButton {
-    id: u32,
-    text: String,
-    rect: Rect,
-    enabled: bool,
-}
+use crate::ui::Column;
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum MenuChoice {
     Continue,
     NewGame,
+    Campaign,
+    Daily,
+    HighScores,
+    Settings,
     Exit,
 }
 
-impl MenuButton {
-    pub fn new(id: u32, text: &str, x: i32, y: i32, w: u32, h: u32, enabled: bool) -> Self {
-        Self {
-            id,
-            text: text.to_string(),
-            rect: Rect::new(x, y, w, h),
-            enabled,
-        }
-    }
-    fn clicked(&self, x: i32, y: i32) -> bool {
-        self.rect.contains_point((x, y)) && self.enabled
-    }
-    fn hovered(&self, x: i32, y: i32) -> bool {
-        if self.rect.contains_point((x, y)) && self.enabled {
-            true
-        } else {
-            false
-        }
-    }
+fn buttons() -> Column<MenuChoice> {
+    Column::new(100, 80, 250, 65)
+        .button("Continue Game", MenuChoice::Continue)
+        .enabled(savegame_exists())
+        .button("New Game", MenuChoice::NewGame)
+        .button("Campaign", MenuChoice::Campaign)
+        .button("Daily challenge", MenuChoice::Daily)
+        .button("High scores", MenuChoice::HighScores)
+        .button("Settings", MenuChoice::Settings)
+        .button("Exit", MenuChoice::Exit)
 }
 
 pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<MenuChoice, String> {
     let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
-    let mut font = ttf_context.load_font("resources/COUR.TTF", 20)?;
+    let settings = load_settings();
+    let theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
     font.set_style(sdl2::ttf::FontStyle::BOLD);
 
     let mut event_pump = sdl_context.event_pump()?;
     let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone)
+        .map_err(|e| println!("Menu: No game controller support: {}", e))
+        .ok();
+    let mut ui = buttons();
 
     loop {
-        let buttons = vec![
-            MenuButton::new(10, "Continue Game", 100, 100, 200, 75, savegame_exists()),
-            MenuButton::new(20, "New Game", 100, 200, 200, 75, true),
-            MenuButton::new(30, "High scores", 100, 300, 200, 75, false),
-            MenuButton::new(90, "Exit", 100, 400, 200, 75, true),
-        ];
-        for button in &buttons {
-            let mut color = sdl2::pixels::Color::RGBA(255, 255, 255, 200);
-
-            if button.hovered(event_pump.mouse_state().x(), event_pump.mouse_state().y()) {
-                color = sdl2::pixels::Color::RGBA(255, 255, 90, 200);
-            } 
-            
-            if button.enabled == false {
-                color = sdl2::pixels::Color::RGBA(90, 90, 90, 200);
-            }
-
-            let surface = font
-                .render(button.text.as_str())
-                .blended(color)
-                .map_err(|e| e.to_string())?;
-            let texture = texture_creator
-                .create_texture_from_surface(&surface)
-                .map_err(|e| e.to_string())?;
-
-            canvas.copy(&texture, None, button.rect)?;
-        }
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
 
         for event in event_pump.poll_iter() {
-            match event {
-                
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
+            let action = match event {
+                Event::Quit { .. } => {
                     return Ok(MenuChoice::Exit);
                 }
-
-                Event::MouseButtonDown { x, y, .. } => {
-                    for button in &buttons {
-                        if button.clicked(x, y) {
-                            match button.id {
-                                10 => {
-                                    return Ok(MenuChoice::Continue);
-                                }
-                                20 => {
-                                    return Ok(MenuChoice::NewGame);
-                                }
-                                90 => {
-                                    return Ok(MenuChoice::Exit);
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-                _ => {}
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => settings.key_bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if action == Some(Action::Quit) {
+                return Ok(MenuChoice::Exit);
+            }
+            if let Some(choice) = ui.handle_event(&event, action) {
+                return Ok(choice);
             }
         }
 
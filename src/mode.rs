@@ -0,0 +1,162 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::Block;
+
+/// Ticks between two rings of the shrinking arena turning into walls
+pub const SHRINK_PERIOD: u32 = 150;
+
+/// Game time of a time attack, in ms
+pub const TIME_ATTACK_MS: u64 = 120_000;
+
+/// Blocks the snake must reach to finish a race
+pub const RACE_LENGTH: usize = 30;
+
+///
+/// Rules of a game, on top of the difficulty and the map
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Classic, // Score by eating, until the snake dies
+    Shrinking,  // The grid closes in from the edges, score by surviving and eating
+    TimeAttack, // Highest score before the time runs out
+    Race,       // Reach a length as fast as possible
+    Zen,        // No death and no speed up, until the player ends the game
+    Campaign,   // Stages played in order on maps, each cleared by eating enough food
+}
+
+impl GameMode {
+    pub fn all() -> [GameMode; 6] {
+        [
+            GameMode::Classic,
+            GameMode::Shrinking,
+            GameMode::TimeAttack,
+            GameMode::Race,
+            GameMode::Zen,
+            GameMode::Campaign,
+        ]
+    }
+
+    /// Modes of new games, the campaign is started from its own screen
+    pub fn free_play() -> Vec<GameMode> {
+        GameMode::all()
+            .into_iter()
+            .filter(|mode| *mode != GameMode::Campaign)
+            .collect()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Shrinking => "Shrinking arena",
+            GameMode::TimeAttack => "Time attack",
+            GameMode::Race => "Race",
+            GameMode::Zen => "Zen",
+            GameMode::Campaign => "Campaign",
+        }
+    }
+
+    /// Whether collisions kill the snake
+    pub fn deadly(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    /// Whether the speed goes up with the level
+    pub fn speeds_up(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    /// Game time before the game ends, in ms
+    pub fn time_limit(&self) -> Option<u64> {
+        match self {
+            GameMode::TimeAttack => Some(TIME_ATTACK_MS),
+            _ => None,
+        }
+    }
+
+    /// Length that wins the game
+    pub fn target_length(&self) -> Option<usize> {
+        match self {
+            GameMode::Race => Some(RACE_LENGTH),
+            _ => None,
+        }
+    }
+
+    /// Whether the high scores rank the fastest games instead of the highest scores
+    pub fn ranks_by_time(&self) -> bool {
+        *self == GameMode::Race
+    }
+
+    /// Outer rings of the grid that are walls after some ticks
+    pub fn closed_rings(&self, ticks: u32) -> u32 {
+        match self {
+            GameMode::Shrinking => ticks / SHRINK_PERIOD,
+            _ => 0,
+        }
+    }
+
+    /// Ticks before the next ring closes, None if the grid never shrinks
+    pub fn next_ring_in(&self, ticks: u32) -> Option<u32> {
+        match self {
+            GameMode::Shrinking => Some(SHRINK_PERIOD - ticks % SHRINK_PERIOD),
+            _ => None,
+        }
+    }
+}
+
+/// Game time as minutes and seconds, such as 1:05
+pub fn format_time(ms: u64) -> String {
+    let seconds = ms / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+///
+/// Columns and rows still open once the outer rings are closed
+/// The ranges are empty when the whole grid is closed
+///
+pub fn open_area(grid_size: (u32, u32), rings: u32) -> (Range<u32>, Range<u32>) {
+    (
+        rings..grid_size.0.saturating_sub(rings),
+        rings..grid_size.1.saturating_sub(rings),
+    )
+}
+
+/// Whether a block is still open once the outer rings are closed
+pub fn is_open(grid_size: (u32, u32), rings: u32, block: &Block) -> bool {
+    let (columns, rows) = open_area(grid_size, rings);
+    columns.contains(&block.0) && rows.contains(&block.1)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_closed_rings() {
+        assert_eq!(GameMode::Classic.closed_rings(10 * SHRINK_PERIOD), 0);
+        assert_eq!(GameMode::Classic.next_ring_in(10), None);
+        assert_eq!(GameMode::Shrinking.closed_rings(SHRINK_PERIOD - 1), 0);
+        assert_eq!(GameMode::Shrinking.closed_rings(2 * SHRINK_PERIOD), 2);
+        assert_eq!(GameMode::Shrinking.next_ring_in(SHRINK_PERIOD - 1), Some(1));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "0:00");
+        assert_eq!(format_time(65_999), "1:05");
+        assert_eq!(format_time(TIME_ATTACK_MS), "2:00");
+    }
+
+    #[test]
+    fn test_open_area() {
+        assert_eq!(open_area((10, 6), 2), (2..8, 2..4));
+        assert!(open_area((10, 6), 3).1.is_empty());
+        assert!(open_area((10, 6), 9).0.is_empty());
+        assert!(is_open((10, 6), 1, &Block(1, 4)));
+        assert!(!is_open((10, 6), 1, &Block(1, 5)));
+        assert!(!is_open((10, 6), 1, &Block(9, 1)));
+    }
+}
@@ -0,0 +1,338 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::video::FullscreenType;
+use sdl2::Sdl;
+use snake::difficulty::{Difficulty, SpeedCurve, CUSTOM};
+use snake::entity::{BorderMode, ControlScheme};
+use snake::gamepad::Gamepads;
+use snake::input::Action;
+use snake::map::Map;
+use snake::mode::GameMode;
+use snake::settings::{load_settings, save_settings, Settings};
+use snake::theme::{ColorVision, Theme};
+
+use crate::controls;
+use crate::ui::Column;
+
+/// Grid sizes offered in the settings, they all fill the window
+const GRID_SIZES: [(u32, u32); 4] = [(20, 15), (40, 30), (80, 60), (100, 75)];
+
+/// Pages of the settings screen
+const PAGES: [&str; 3] = ["Game", "Display", "Controls"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum SettingsChoice {
+    Page(usize),
+    Mode(usize),
+    Map(usize),
+    GridSize(usize),
+    Difficulty(usize),
+    Speed(i32),
+    Curve(usize),
+    Walls(bool),
+    Theme(usize),
+    ColorVision(usize),
+    ShapeMarkers(bool),
+    SmoothMovement(bool),
+    Volume(i32),
+    Fullscreen(bool),
+    ControlScheme(usize),
+    Controls,
+    Back,
+}
+
+/// No map, then every map
+fn map_names(maps: &[Map]) -> Vec<String> {
+    let mut names = vec![String::from("None")];
+    names.extend(maps.iter().map(|m| m.name.clone()));
+    names
+}
+
+/// Presets, then the custom difficulty
+fn difficulty_names() -> Vec<String> {
+    let mut names: Vec<String> = Difficulty::presets().into_iter().map(|d| d.name).collect();
+    names.push(String::from(CUSTOM));
+    names
+}
+
+///
+/// The speed, curve and walls can only be changed for the custom difficulty
+/// The grid size can't be changed with a map, it has its own
+///
+fn game_widgets(
+    ui: Column<SettingsChoice>,
+    settings: &Settings,
+    maps: &[Map],
+) -> Column<SettingsChoice> {
+    let map = maps
+        .iter()
+        .position(|m| Some(&m.name) == settings.map.as_ref())
+        .map_or(0, |i| i + 1);
+    let grid_sizes = GRID_SIZES
+        .iter()
+        .map(|(w, h)| format!("{} x {}", w, h))
+        .collect();
+    let grid = GRID_SIZES
+        .iter()
+        .position(|size| *size == settings.grid_size)
+        .unwrap_or(2);
+    let difficulties = difficulty_names();
+    let difficulty = settings.difficulty();
+    let index = difficulties
+        .iter()
+        .position(|name| *name == difficulty.name)
+        .unwrap_or(0);
+    let custom = difficulty.name == CUSTOM;
+    let curves = SpeedCurve::all()
+        .iter()
+        .map(|c| c.label().to_string())
+        .collect();
+    let curve = SpeedCurve::all()
+        .iter()
+        .position(|c| *c == difficulty.speed_curve)
+        .unwrap_or(0);
+    let modes = GameMode::free_play()
+        .iter()
+        .map(|m| m.label().to_string())
+        .collect();
+    let mode = GameMode::free_play()
+        .iter()
+        .position(|m| *m == settings.mode)
+        .unwrap_or(0);
+
+    ui.list("Mode", modes, mode, SettingsChoice::Mode)
+        .list("Map", map_names(maps), map, SettingsChoice::Map)
+        .list("Grid size", grid_sizes, grid, SettingsChoice::GridSize)
+        .enabled(map == 0)
+        .list(
+            "Difficulty",
+            difficulties,
+            index,
+            SettingsChoice::Difficulty,
+        )
+        .slider(
+            "Starting tick (ms)",
+            difficulty.initial_speed as i32,
+            (40, 200, 10),
+            SettingsChoice::Speed,
+        )
+        .enabled(custom)
+        .list("Speed curve", curves, curve, SettingsChoice::Curve)
+        .enabled(custom)
+        .toggle(
+            "Walls",
+            difficulty.border_mode == BorderMode::Walls,
+            SettingsChoice::Walls,
+        )
+        .enabled(custom)
+}
+
+fn display_widgets(
+    ui: Column<SettingsChoice>,
+    settings: &Settings,
+    themes: &[Theme],
+) -> Column<SettingsChoice> {
+    let names = themes.iter().map(|t| t.name.clone()).collect();
+    let theme = themes
+        .iter()
+        .position(|t| t.name == settings.theme)
+        .unwrap_or(0);
+    let color_visions = ColorVision::all()
+        .iter()
+        .map(|c| c.label().to_string())
+        .collect();
+    let color_vision = ColorVision::all()
+        .iter()
+        .position(|c| *c == settings.color_vision)
+        .unwrap_or(0);
+
+    ui.list("Theme", names, theme, SettingsChoice::Theme)
+        .list(
+            "Color vision",
+            color_visions,
+            color_vision,
+            SettingsChoice::ColorVision,
+        )
+        .toggle(
+            "Shape markers",
+            settings.shape_markers,
+            SettingsChoice::ShapeMarkers,
+        )
+        .toggle(
+            "Smooth movement",
+            settings.smooth_movement,
+            SettingsChoice::SmoothMovement,
+        )
+        .slider(
+            "Volume",
+            settings.volume as i32,
+            (0, 100, 10),
+            SettingsChoice::Volume,
+        )
+        .toggle(
+            "Fullscreen",
+            settings.fullscreen,
+            SettingsChoice::Fullscreen,
+        )
+}
+
+fn control_widgets(ui: Column<SettingsChoice>, settings: &Settings) -> Column<SettingsChoice> {
+    let schemes = ControlScheme::all()
+        .iter()
+        .map(|s| s.label().to_string())
+        .collect();
+    let scheme = ControlScheme::all()
+        .iter()
+        .position(|s| *s == settings.control_scheme)
+        .unwrap_or(0);
+
+    ui.list(
+        "Control scheme",
+        schemes,
+        scheme,
+        SettingsChoice::ControlScheme,
+    )
+    .button("Key bindings", SettingsChoice::Controls)
+}
+
+///
+/// The settings are split in pages so each one fits in the window
+///
+fn widgets(
+    settings: &Settings,
+    themes: &[Theme],
+    maps: &[Map],
+    page: usize,
+) -> Column<SettingsChoice> {
+    let pages = PAGES.iter().map(|p| p.to_string()).collect();
+    let ui = Column::new(100, 20, 600, 40).list("Page", pages, page, SettingsChoice::Page);
+    let ui = match page {
+        0 => game_widgets(ui, settings, maps),
+        1 => display_widgets(ui, settings, themes),
+        _ => control_widgets(ui, settings),
+    };
+    let ui = ui.button("Back", SettingsChoice::Back);
+    if page == 0 {
+        ui.label("Game settings apply to the next new game")
+    } else {
+        ui
+    }
+}
+
+pub fn set_fullscreen(canvas: &mut WindowCanvas, fullscreen: bool) -> Result<(), String> {
+    let mode = if fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Off
+    };
+    canvas.window_mut().set_fullscreen(mode)
+}
+
+///
+/// Settings screen
+///
+/// Every change is saved right away. The theme, color vision and fullscreen apply
+/// immediately, the grid, difficulty and volume with the next game.
+///
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), String> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let mut settings = load_settings();
+    let themes = Theme::all();
+    let maps = Map::all();
+    let mut theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone).ok();
+    let mut page = 0;
+    let mut ui = widgets(&settings, &themes, &maps, page);
+
+    loop {
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
+
+        for event in event_pump.poll_iter() {
+            let action = match event {
+                Event::Quit { .. } => return Ok(()),
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => settings.key_bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if action == Some(Action::Quit) {
+                return Ok(());
+            }
+            let Some(choice) = ui.handle_event(&event, action) else {
+                continue;
+            };
+            match choice {
+                SettingsChoice::Page(i) => {
+                    page = i;
+                    ui = widgets(&settings, &themes, &maps, page);
+                    continue;
+                }
+                SettingsChoice::Mode(i) => settings.mode = GameMode::free_play()[i],
+                SettingsChoice::Map(i) => {
+                    settings.map = i.checked_sub(1).map(|i| maps[i].name.clone());
+                    let focus = ui.focus();
+                    ui = widgets(&settings, &themes, &maps, page);
+                    ui.set_focus(focus);
+                }
+                SettingsChoice::GridSize(i) => settings.grid_size = GRID_SIZES[i],
+                SettingsChoice::Difficulty(i) => {
+                    settings.difficulty = difficulty_names()[i].clone();
+                    // Show the values of the new difficulty
+                    let focus = ui.focus();
+                    ui = widgets(&settings, &themes, &maps, page);
+                    ui.set_focus(focus);
+                }
+                SettingsChoice::Speed(speed) => settings.initial_speed = speed as u32,
+                SettingsChoice::Curve(i) => settings.speed_curve = SpeedCurve::all()[i],
+                SettingsChoice::Walls(walls) => {
+                    settings.border_mode = if walls {
+                        BorderMode::Walls
+                    } else {
+                        BorderMode::Wrap
+                    };
+                }
+                SettingsChoice::Theme(i) => {
+                    settings.theme = themes[i].name.clone();
+                    theme = themes[i].clone().with_color_vision(settings.color_vision);
+                    font = ttf_context.load_font(&theme.font, theme.font_size)?;
+                    font.set_style(sdl2::ttf::FontStyle::BOLD);
+                }
+                SettingsChoice::ColorVision(i) => {
+                    settings.color_vision = ColorVision::all()[i];
+                    theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+                }
+                SettingsChoice::ShapeMarkers(markers) => settings.shape_markers = markers,
+                SettingsChoice::SmoothMovement(smooth) => settings.smooth_movement = smooth,
+                SettingsChoice::Volume(volume) => settings.volume = volume as u8,
+                SettingsChoice::Fullscreen(fullscreen) => {
+                    settings.fullscreen = fullscreen;
+                    set_fullscreen(canvas, fullscreen)?;
+                }
+                SettingsChoice::ControlScheme(i) => {
+                    settings.control_scheme = ControlScheme::all()[i];
+                }
+                SettingsChoice::Controls => {
+                    controls::run(sdl_context, canvas)?;
+                    // The controls screen saved the new key bindings
+                    settings = load_settings();
+                    continue;
+                }
+                SettingsChoice::Back => return Ok(()),
+            }
+            save_settings(&settings)?;
+        }
+
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
@@ -0,0 +1,208 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::Block;
+use crate::theme::Shape;
+
+/// One chance in this many ticks to spawn a power-up, when there is none on the grid
+pub const SPAWN_CHANCE: u32 = 120;
+
+/// Ticks before a power-up nobody picked up disappears
+pub const LIFETIME: u32 = 80;
+
+/// Distance from the head, in blocks, within which the magnet pulls the food
+pub const MAGNET_RANGE: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    Ghost,  // The snake passes through its own body
+    Magnet, // Nearby food moves toward the head
+    Shield, // Absorbs the next fatal collision
+}
+
+impl PowerUpKind {
+    pub fn all() -> [PowerUpKind; 3] {
+        [PowerUpKind::Ghost, PowerUpKind::Magnet, PowerUpKind::Shield]
+    }
+
+    /// Sprite file name, looked up in the theme sprite directory
+    pub fn texture(&self) -> &'static str {
+        match self {
+            PowerUpKind::Ghost => "ghost.png",
+            PowerUpKind::Magnet => "magnet.png",
+            PowerUpKind::Shield => "shield.png",
+        }
+    }
+
+    /// Drawn over the power-up when shape markers are enabled
+    pub fn shape(&self) -> Shape {
+        match self {
+            PowerUpKind::Ghost => Shape::Circle,
+            PowerUpKind::Magnet => Shape::Triangle,
+            PowerUpKind::Shield => Shape::Square,
+        }
+    }
+
+    /// Ticks the power-up lasts once picked up, None if it lasts until used
+    pub fn duration(&self) -> Option<u32> {
+        match self {
+            PowerUpKind::Ghost => Some(40),
+            PowerUpKind::Magnet => Some(60),
+            PowerUpKind::Shield => None,
+        }
+    }
+}
+
+///
+/// Power-up waiting on the grid to be picked up
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerUp {
+    pub kind: PowerUpKind, // What it does once picked up
+    pub position: Block,   // Position on the grid
+    pub ticks_left: u32,   // Ticks before it disappears
+}
+
+impl PowerUp {
+    ///
+    /// Random power-up on a free block of the grid
+    /// None if the grid is full
+    ///
+    pub fn spawn(
+        rng: &mut impl Rng,
+        grid_size: (u32, u32),
+        occupied: impl Fn(&Block) -> bool,
+    ) -> Option<PowerUp> {
+        let free: Vec<Block> = (0..grid_size.1)
+            .flat_map(|y| (0..grid_size.0).map(move |x| Block(x, y)))
+            .filter(|block| !occupied(block))
+            .collect();
+        if free.is_empty() {
+            return None;
+        }
+        let kinds = PowerUpKind::all();
+        Some(PowerUp {
+            kind: kinds[rng.random_range(0..kinds.len())],
+            position: free[rng.random_range(0..free.len())],
+            ticks_left: LIFETIME,
+        })
+    }
+
+    /// Count down the lifetime, returns true when the power-up disappears
+    pub fn expire(&mut self) -> bool {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        self.ticks_left == 0
+    }
+}
+
+///
+/// Power-ups picked up by the snake
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivePowerUps {
+    pub ghost: u32,   // Ticks left in ghost mode
+    pub magnet: u32,  // Ticks left with the magnet
+    pub shield: bool, // Whether the next fatal collision is absorbed
+}
+
+impl ActivePowerUps {
+    /// Start a power-up, picking up the same kind again restarts its timer
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        let ticks = kind.duration().unwrap_or(0);
+        match kind {
+            PowerUpKind::Ghost => self.ghost = ticks,
+            PowerUpKind::Magnet => self.magnet = ticks,
+            PowerUpKind::Shield => self.shield = true,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.ghost = self.ghost.saturating_sub(1);
+        self.magnet = self.magnet.saturating_sub(1);
+    }
+
+    ///
+    /// Use the shield against a fatal collision
+    /// Returns false if there is no shield, the collision is fatal
+    ///
+    pub fn use_shield(&mut self) -> bool {
+        std::mem::replace(&mut self.shield, false)
+    }
+
+    /// Short texts for the HUD, with the ticks left
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels = Vec::new();
+        if self.ghost > 0 {
+            labels.push(format!("Ghost {}", self.ghost));
+        }
+        if self.magnet > 0 {
+            labels.push(format!("Magnet {}", self.magnet));
+        }
+        if self.shield {
+            labels.push(String::from("Shield"));
+        }
+        labels
+    }
+}
+
+///
+/// Next position of food pulled by the magnet, one block toward the head
+/// None if the food is out of range, or already next to the head
+///
+pub fn pull(food: &Block, head: &Block) -> Option<Block> {
+    let dx = head.0 as i32 - food.0 as i32;
+    let dy = head.1 as i32 - food.1 as i32;
+    let distance = dx.unsigned_abs() + dy.unsigned_abs();
+    if distance <= 1 || distance > MAGNET_RANGE {
+        return None;
+    }
+    if dx.abs() >= dy.abs() {
+        Some(Block((food.0 as i32 + dx.signum()) as u32, food.1))
+    } else {
+        Some(Block(food.0, (food.1 as i32 + dy.signum()) as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_spawn() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let power_up = PowerUp::spawn(&mut rng, (4, 4), |block| block.0 < 3).unwrap();
+            assert_eq!(power_up.position.0, 3);
+            assert_eq!(power_up.ticks_left, LIFETIME);
+        }
+        assert_eq!(PowerUp::spawn(&mut rng, (4, 4), |_| true), None);
+    }
+
+    #[test]
+    fn test_active_power_ups() {
+        let mut active = ActivePowerUps::default();
+        assert!(!active.use_shield());
+        active.activate(PowerUpKind::Ghost);
+        active.activate(PowerUpKind::Shield);
+        active.tick();
+        assert_eq!(active.labels(), vec!["Ghost 39", "Shield"]);
+        assert!(active.use_shield());
+        assert!(!active.use_shield());
+        for _ in 0..39 {
+            active.tick();
+        }
+        assert!(active.labels().is_empty());
+    }
+
+    #[test]
+    fn test_pull() {
+        assert_eq!(pull(&Block(5, 5), &Block(8, 6)), Some(Block(6, 5)));
+        assert_eq!(pull(&Block(5, 5), &Block(4, 2)), Some(Block(5, 4)));
+        assert_eq!(pull(&Block(5, 5), &Block(5, 6)), None);
+        assert_eq!(pull(&Block(0, 0), &Block(10, 10)), None);
+    }
+}
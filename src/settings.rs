@@ -0,0 +1,128 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+use crate::difficulty::{Difficulty, SpeedCurve};
+use crate::entity::{BorderMode, ControlScheme};
+use crate::gamepad::DEFAULT_DEADZONE;
+use crate::input::KeyBindings;
+use crate::mode::GameMode;
+use crate::theme::ColorVision;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+///
+/// User settings, kept between sessions
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub player_name: String,           // Name recorded in the high scores
+    pub theme: String,                 // Name of the selected theme
+    pub color_vision: ColorVision,     // Palette adapted to color blindness
+    pub shape_markers: bool,           // Draw shapes over food and hazards
+    pub smooth_movement: bool,         // Interpolate the snake between two ticks
+    pub control_scheme: ControlScheme, // Absolute or relative controls
+    #[serde(deserialize_with = "or_default")]
+    pub key_bindings: KeyBindings, // Keys bound to each action
+    pub gamepad_deadzone: i16,         // Analog stick deadzone, out of 32767
+    pub grid_size: (u32, u32),         // Grid of new games, in cells
+    pub difficulty: String,            // Name of the difficulty preset, or Custom
+    pub initial_speed: u32,            // Custom difficulty: first tick length, in ms
+    pub speed_curve: SpeedCurve,       // Custom difficulty: how the speed goes up
+    pub border_mode: BorderMode,       // Custom difficulty: wrap or walls at the edge
+    pub map: Option<String>,           // Map of new games, an open grid if None
+    pub mode: GameMode,                // Rules of new games
+    pub volume: u8,                    // Master volume, in percent
+    pub fullscreen: bool,              // Fullscreen or windowed
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            player_name: String::from("Player"),
+            theme: String::from("Classic"),
+            color_vision: ColorVision::Normal,
+            shape_markers: false,
+            smooth_movement: false,
+            control_scheme: ControlScheme::Absolute,
+            key_bindings: KeyBindings::default(),
+            gamepad_deadzone: DEFAULT_DEADZONE,
+            grid_size: (80, 60),
+            difficulty: Difficulty::default().name,
+            initial_speed: 100,
+            speed_curve: SpeedCurve::Linear,
+            border_mode: BorderMode::Wrap,
+            map: None,
+            mode: GameMode::Classic,
+            volume: 100,
+            fullscreen: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Difficulty of new games
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::find(&self.difficulty, self)
+    }
+}
+
+///
+/// Use the default value when a setting is invalid, instead of dropping the whole file
+///
+fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).unwrap_or_else(|e| {
+        println!("Invalid setting ({}), using the default", e);
+        T::default()
+    }))
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
+    println!("Saving settings to {}", SETTINGS_FILE);
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(SETTINGS_FILE, json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+///
+/// Load the user settings
+/// Missing file or missing values fall back to the defaults
+///
+pub fn load_settings() -> Settings {
+    let json = match std::fs::read_to_string(SETTINGS_FILE) {
+        Ok(json) => json,
+        Err(_) => return Settings::default(),
+    };
+    println!("Loading settings from {}", SETTINGS_FILE);
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        println!("Invalid settings file ({}), using defaults", e);
+        Settings::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_partial_settings() {
+        let settings: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, Settings::default());
+        let settings: Settings = serde_json::from_str(r#"{"theme": "Game Boy"}"#).unwrap();
+        assert_eq!(settings.theme, "Game Boy");
+    }
+
+    #[test]
+    fn test_invalid_key_bindings() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"theme": "Game Boy", "key_bindings": {"Up": ["Escape"]}}"#)
+                .unwrap();
+        assert_eq!(settings.theme, "Game Boy");
+        assert_eq!(settings.key_bindings, KeyBindings::default());
+    }
+}
@@ -1,20 +1,107 @@
-use rodio::{source::Source, Decoder};
+use rodio::buffer::SamplesBuffer;
+use rodio::source::ChannelVolume;
 use rodio::OutputStreamHandle;
+use rodio::{source::Source, Decoder};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{self, Debug, Formatter};
 use std::fs::File;
 use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::synth;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+const DEFAULT_SOUND_PACK: &str = "resources/sounds.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Sound {
     Eat,
     Start,
     GameOver,
+    LevelUp,
+    Turn,
+    Pause,
+    FoodExpire,
+    PowerUp,
+    Shield,
+}
+impl Sound {
+    pub fn all() -> [Sound; 9] {
+        [
+            Sound::Eat,
+            Sound::Start,
+            Sound::GameOver,
+            Sound::LevelUp,
+            Sound::Turn,
+            Sound::Pause,
+            Sound::FoodExpire,
+            Sound::PowerUp,
+            Sound::Shield,
+        ]
+    }
+}
+
+///
+/// How a sound is played
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playback {
+    pub pan: f32,   // Stereo position, from -1.0 (left) to 1.0 (right)
+    pub pitch: f32, // Playback speed ratio, 1.0 is the original pitch
+}
+impl Default for Playback {
+    fn default() -> Playback {
+        Playback {
+            pan: 0.0,
+            pitch: 1.0,
+        }
+    }
+}
+impl Playback {
+    /// Volume of the left and right channels (constant power panning)
+    pub fn channel_volumes(&self) -> [f32; 2] {
+        let angle = (self.pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+        [angle.cos(), angle.sin()]
+    }
+}
+
+///
+/// A sound pack maps sounds to audio files
+///
+/// The manifest is a json file, file names are relative to the manifest:
+/// `{ "name": "default", "sounds": { "Eat": "eat.ogg" } }`
+/// Sounds missing from the pack are synthesized.
+///
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SoundPack {
+    pub name: String,
+    #[serde(default)]
+    pub sounds: HashMap<Sound, PathBuf>,
+}
+impl SoundPack {
+    /// An empty pack, every sound is synthesized
+    pub fn synthesized() -> SoundPack {
+        SoundPack {
+            name: String::from("synthesized"),
+            sounds: HashMap::new(),
+        }
+    }
+
+    pub fn load(manifest: &str) -> Result<SoundPack, String> {
+        let json = std::fs::read_to_string(manifest).map_err(|e| e.to_string())?;
+        let mut pack: SoundPack = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        let base = Path::new(manifest).parent().unwrap_or(Path::new(""));
+        for file in pack.sounds.values_mut() {
+            *file = base.join(&file);
+        }
+        Ok(pack)
+    }
 }
 
 pub struct SoundSystem {
     stream_handle: Box<OutputStreamHandle>,
-    sounds: HashMap<Sound, String>,
+    pack: SoundPack,
+    volume: f32, // Master volume, from 0 to 1
 }
 impl Debug for SoundSystem {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -23,32 +110,99 @@ impl Debug for SoundSystem {
 }
 impl SoundSystem {
     pub fn new(stream_handle: OutputStreamHandle) -> SoundSystem {
-        let sounds = HashMap::from([
-            (Sound::Start, String::from("resources/start.ogg")),
-            (Sound::Eat, String::from("resources/eat.ogg")),
-            (Sound::GameOver, String::from("resources/gameover.ogg")),
-        ]);
+        let pack = SoundPack::load(DEFAULT_SOUND_PACK).unwrap_or_else(|e| {
+            println!(
+                "SoundSystem: Failed to load sound pack ({}), using synthesizer",
+                e
+            );
+            SoundPack::synthesized()
+        });
+        SoundSystem::with_pack(stream_handle, pack)
+    }
+    pub fn with_pack(stream_handle: OutputStreamHandle, pack: SoundPack) -> SoundSystem {
+        println!("SoundSystem: Using sound pack {}", pack.name);
         SoundSystem {
             stream_handle: Box::new(stream_handle),
-            sounds,
+            pack,
+            volume: 1.0,
         }
     }
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
     pub fn play_snd(&self, snd: Sound) -> Result<(), rodio::PlayError> {
-        // Get the file form the hashmap
-        let filename = self.sounds.get(&snd).unwrap();
-        println!("Playing sound: {}", filename);
-        let file = BufReader::new(File::open(filename).unwrap());
-        let source = Decoder::new(file).unwrap();
-        self.stream_handle.play_raw(source.convert_samples())
+        self.play_snd_with(snd, Playback::default())
+    }
+    pub fn play_snd_with(&self, snd: Sound, playback: Playback) -> Result<(), rodio::PlayError> {
+        // Get the file from the sound pack, fall back to the synthesizer
+        if let Some(filename) = self.pack.sounds.get(&snd) {
+            match File::open(filename)
+                .map_err(|e| e.to_string())
+                .and_then(|f| Decoder::new(BufReader::new(f)).map_err(|e| e.to_string()))
+            {
+                Ok(source) => {
+                    println!("Playing sound: {} {:?}", filename.display(), playback);
+                    return self.play_source(source.convert_samples(), playback);
+                }
+                Err(e) => println!("Failed to open sound {}: {}", filename.display(), e),
+            }
+        }
+        println!("Playing synthesized sound: {:?} {:?}", snd, playback);
+        let samples = synth::render(&synth::preset(snd), synth::SAMPLE_RATE);
+        self.play_source(SamplesBuffer::new(1, synth::SAMPLE_RATE, samples), playback)
+    }
+
+    /// Apply the pitch and the panning, then play the source
+    fn play_source<S>(&self, source: S, playback: Playback) -> Result<(), rodio::PlayError>
+    where
+        S: Source<Item = f32> + Send + 'static,
+    {
+        // The source is mixed down to mono before panning, keep the level unchanged
+        let scale = self.volume / source.channels() as f32;
+        let volumes = playback.channel_volumes().map(|v| v * scale).to_vec();
+        let source = source.speed(playback.pitch);
+        self.stream_handle
+            .play_raw(ChannelVolume::new(source, volumes))
     }
 }
 
+#[cfg(test)]
 mod tests {
 
-    // use super::*;
+    use super::*;
+
+    #[test]
+    fn test_load_default_pack() {
+        let pack = SoundPack::load(DEFAULT_SOUND_PACK).unwrap();
+        assert_eq!(
+            pack.sounds.get(&Sound::Eat),
+            Some(&PathBuf::from("resources/eat.ogg"))
+        );
+        assert!(pack.sounds.values().all(|f| f.exists()));
+    }
+
+    #[test]
+    fn test_playback_channel_volumes() {
+        let [left, right] = Playback::default().channel_volumes();
+        assert!((left - right).abs() < 0.001);
+        let [left, right] = Playback {
+            pan: -1.0,
+            pitch: 1.0,
+        }
+        .channel_volumes();
+        assert!((left - 1.0).abs() < 0.001);
+        assert!(right.abs() < 0.001);
+        let [left, right] = Playback {
+            pan: 2.0,
+            pitch: 1.0,
+        }
+        .channel_volumes();
+        assert!(left.abs() < 0.001);
+        assert!((right - 1.0).abs() < 0.001);
+    }
 
-    // #[test]
-    // fn test_play_snd() {
-    //     play_snd();
-    // }
+    #[test]
+    fn test_load_missing_pack() {
+        assert!(SoundPack::load("resources/missing.json").is_err());
+    }
 }
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+use snake::campaign::{load_progress, Campaign, Progress};
+use snake::gamepad::Gamepads;
+use snake::input::Action;
+use snake::settings::load_settings;
+use snake::theme::Theme;
+
+use crate::ui::Column;
+
+#[derive(Debug, Clone, PartialEq)]
+enum StageChoice {
+    Stage(usize),
+    Back,
+}
+
+///
+/// Stages of the campaign, the locked ones can't be chosen
+///
+fn widgets(campaign: &Campaign, progress: &Progress) -> Column<StageChoice> {
+    let mut ui = Column::new(100, 20, 600, 50).label("Campaign");
+    for (index, stage) in campaign.stages().iter().enumerate() {
+        let text = format!("{}. {} - eat {}", index + 1, stage.map, stage.goal);
+        ui = ui
+            .button(&text, StageChoice::Stage(index))
+            .enabled(progress.is_unlocked(index));
+    }
+    if campaign.stages().is_empty() {
+        ui = ui.label("No campaign found");
+    }
+    ui.button("Back", StageChoice::Back)
+}
+
+///
+/// Campaign screen, returns the stage to start from, None to go back to the menu
+///
+pub fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<Option<usize>, String> {
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let settings = load_settings();
+    let theme = Theme::find(&settings.theme).with_color_vision(settings.color_vision);
+    let mut font = ttf_context.load_font(&theme.font, theme.font_size)?;
+    font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+    let mut event_pump = sdl_context.event_pump()?;
+    let texture_creator = canvas.texture_creator();
+    let mut gamepads = Gamepads::new(sdl_context, settings.gamepad_deadzone).ok();
+
+    let mut ui = widgets(&Campaign::load_default(), &load_progress());
+
+    loop {
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        ui.draw(canvas, &font, &texture_creator, &theme)?;
+
+        for event in event_pump.poll_iter() {
+            let action = match event {
+                Event::Quit { .. } => return Ok(None),
+                Event::KeyDown {
+                    keycode: Some(key), ..
+                } => settings.key_bindings.action(key),
+                _ => gamepads.as_mut().and_then(|g| g.handle_event(&event)),
+            };
+            if action == Some(Action::Quit) {
+                return Ok(None);
+            }
+            match ui.handle_event(&event, action) {
+                Some(StageChoice::Stage(stage)) => return Ok(Some(stage)),
+                Some(StageChoice::Back) => return Ok(None),
+                None => {}
+            }
+        }
+
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::sound::Sound;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wave {
+    Square,
+    Triangle,
+    Sawtooth,
+    Noise,
+}
+
+/// A single chiptune note: a waveform sliding from one frequency to another
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blip {
+    pub wave: Wave,
+    pub start_freq: f32, // Frequency at the start of the note, in Hz
+    pub end_freq: f32,   // Frequency at the end of the note, in Hz
+    pub duration_ms: u32,
+    pub volume: f32, // Peak amplitude, between 0.0 and 1.0
+}
+
+impl Blip {
+    pub fn new(wave: Wave, start_freq: f32, end_freq: f32, duration_ms: u32) -> Blip {
+        Blip {
+            wave,
+            start_freq,
+            end_freq,
+            duration_ms,
+            volume: 0.3,
+        }
+    }
+
+    /// Render the note as mono samples
+    ///
+    /// The frequency slides linearly and the amplitude decays linearly to zero
+    /// so consecutive notes don't click.
+    pub fn render(&self, sample_rate: u32) -> Vec<f32> {
+        let len = (sample_rate as u64 * self.duration_ms as u64 / 1000) as usize;
+        let mut samples = Vec::with_capacity(len);
+        let mut phase: f32 = 0.0;
+        // Small xorshift generator, the noise only has to sound random
+        let mut noise: u32 = 0x1234_5678;
+        let mut noise_value: f32 = 0.0;
+
+        for i in 0..len {
+            let t = i as f32 / len as f32;
+            let freq = self.start_freq + (self.end_freq - self.start_freq) * t;
+            let previous_phase = phase;
+            phase = (phase + freq / sample_rate as f32) % 1.0;
+
+            let value = match self.wave {
+                Wave::Square => {
+                    if phase < 0.5 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Wave::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+                Wave::Sawtooth => 2.0 * phase - 1.0,
+                Wave::Noise => {
+                    // Pick a new random value once per period
+                    if phase < previous_phase || i == 0 {
+                        noise ^= noise << 13;
+                        noise ^= noise >> 17;
+                        noise ^= noise << 5;
+                        noise_value = (noise as f32 / u32::MAX as f32) * 2.0 - 1.0;
+                    }
+                    noise_value
+                }
+            };
+            let envelope = 1.0 - t;
+            samples.push(value * envelope * self.volume);
+        }
+        samples
+    }
+}
+
+/// Render a sequence of notes played one after the other
+pub fn render(blips: &[Blip], sample_rate: u32) -> Vec<f32> {
+    blips.iter().flat_map(|b| b.render(sample_rate)).collect()
+}
+
+/// Built-in chiptune version of every sound
+pub fn preset(sound: Sound) -> Vec<Blip> {
+    match sound {
+        Sound::Eat => vec![
+            Blip::new(Wave::Square, 660.0, 660.0, 40),
+            Blip::new(Wave::Square, 990.0, 1320.0, 60),
+        ],
+        Sound::Start => vec![
+            Blip::new(Wave::Square, 523.0, 523.0, 90),
+            Blip::new(Wave::Square, 659.0, 659.0, 90),
+            Blip::new(Wave::Square, 784.0, 784.0, 90),
+            Blip::new(Wave::Square, 1046.0, 1046.0, 180),
+        ],
+        Sound::GameOver => vec![
+            Blip::new(Wave::Sawtooth, 440.0, 220.0, 200),
+            Blip::new(Wave::Sawtooth, 330.0, 110.0, 400),
+            Blip::new(Wave::Noise, 800.0, 200.0, 200),
+        ],
+        Sound::LevelUp => vec![
+            Blip::new(Wave::Triangle, 523.0, 523.0, 70),
+            Blip::new(Wave::Triangle, 784.0, 784.0, 70),
+            Blip::new(Wave::Triangle, 1046.0, 1568.0, 150),
+        ],
+        Sound::Turn => vec![Blip::new(Wave::Triangle, 220.0, 180.0, 25)],
+        Sound::Pause => vec![
+            Blip::new(Wave::Square, 880.0, 880.0, 50),
+            Blip::new(Wave::Square, 440.0, 440.0, 80),
+        ],
+        Sound::FoodExpire => vec![Blip::new(Wave::Noise, 2000.0, 400.0, 150)],
+        Sound::PowerUp => vec![
+            Blip::new(Wave::Square, 440.0, 880.0, 60),
+            Blip::new(Wave::Square, 880.0, 1760.0, 90),
+        ],
+        Sound::Shield => vec![
+            Blip::new(Wave::Sawtooth, 220.0, 220.0, 40),
+            Blip::new(Wave::Noise, 1200.0, 600.0, 120),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_blip_length() {
+        let blip = Blip::new(Wave::Square, 440.0, 440.0, 100);
+        assert_eq!(blip.render(1000).len(), 100);
+        assert_eq!(blip.render(44100).len(), 4410);
+    }
+
+    #[test]
+    fn test_blip_amplitude() {
+        for wave in [Wave::Square, Wave::Triangle, Wave::Sawtooth, Wave::Noise] {
+            let blip = Blip::new(wave, 440.0, 880.0, 50);
+            let samples = blip.render(SAMPLE_RATE);
+            assert!(samples.iter().all(|s| s.abs() <= blip.volume));
+            assert!(samples.iter().any(|s| *s != 0.0));
+        }
+    }
+
+    #[test]
+    fn test_presets_not_empty() {
+        for sound in Sound::all() {
+            assert!(!render(&preset(sound), SAMPLE_RATE).is_empty());
+        }
+    }
+}
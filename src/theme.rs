@@ -0,0 +1,362 @@
+use std::path::Path;
+
+use sdl2::pixels::Color;
+use serde::{Deserialize, Serialize};
+
+const THEMES_DIR: &str = "resources/themes";
+
+///
+/// Color stored as a hex string in theme files: "#rrggbb" or "#rrggbbaa"
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub u8, pub u8, pub u8, pub u8);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value
+            .strip_prefix('#')
+            .ok_or(format!("Invalid color {}, expected #rrggbb", value))?;
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(format!("Invalid color {}, expected #rrggbb", value));
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("Invalid color {}, expected #rrggbb", value))
+        };
+        let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+        Ok(ThemeColor(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+}
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> String {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.0, color.1, color.2, color.3
+        )
+    }
+}
+impl From<ThemeColor> for Color {
+    fn from(color: ThemeColor) -> Color {
+        Color::RGBA(color.0, color.1, color.2, color.3)
+    }
+}
+
+///
+/// Color vision deficiency the palette is adapted to
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    Monochrome,
+}
+impl ColorVision {
+    pub fn all() -> [ColorVision; 5] {
+        [
+            ColorVision::Normal,
+            ColorVision::Deuteranopia,
+            ColorVision::Protanopia,
+            ColorVision::Tritanopia,
+            ColorVision::Monochrome,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorVision::Normal => "Normal",
+            ColorVision::Deuteranopia => "Deuteranopia",
+            ColorVision::Protanopia => "Protanopia",
+            ColorVision::Tritanopia => "Tritanopia",
+            ColorVision::Monochrome => "Monochrome",
+        }
+    }
+
+    ///
+    /// Snake and food colors for this color vision, None keeps the theme colors
+    /// Based on the Okabe-Ito palette, the two colors also differ in brightness
+    ///
+    fn palette(&self) -> Option<(ThemeColor, ThemeColor)> {
+        match self {
+            ColorVision::Normal => None,
+            ColorVision::Deuteranopia => {
+                Some((ThemeColor(0, 114, 178, 255), ThemeColor(230, 159, 0, 255)))
+            }
+            ColorVision::Protanopia => {
+                Some((ThemeColor(86, 180, 233, 255), ThemeColor(240, 228, 66, 255)))
+            }
+            ColorVision::Tritanopia => {
+                Some((ThemeColor(213, 94, 0, 255), ThemeColor(0, 158, 115, 255)))
+            }
+            ColorVision::Monochrome => Some((
+                ThemeColor(255, 255, 255, 255),
+                ThemeColor(150, 150, 150, 255),
+            )),
+        }
+    }
+}
+
+///
+/// Shape drawn over an object so it can be told apart without colors
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Shape {
+    Circle,
+    Triangle,
+    Square,
+    Diamond,
+    Cross,
+    Plus,
+    Bar,
+    Ring,
+}
+impl Shape {
+    ///
+    /// Pixels covered by the shape in a size x size box
+    ///
+    pub fn mask(&self, size: u32) -> Vec<(i32, i32)> {
+        let size = size as i32;
+        let max = (size - 1) as f32;
+        let center = max / 2.0;
+        let mut points = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let (fx, fy) = (x as f32, y as f32);
+                let inside = match self {
+                    Shape::Circle => {
+                        (fx - center).powi(2) + (fy - center).powi(2) <= (center + 0.5).powi(2)
+                    }
+                    // Pointing up, the base is the bottom row
+                    Shape::Triangle => (fx - center).abs() <= fy / 2.0 + 0.5,
+                    Shape::Square => true,
+                    Shape::Diamond => (fx - center).abs() + (fy - center).abs() <= center + 0.5,
+                    Shape::Cross => (fx - fy).abs() <= 0.5 || (fx + fy - max).abs() <= 0.5,
+                    Shape::Plus => (fx - center).abs() <= 0.5 || (fy - center).abs() <= 0.5,
+                    Shape::Bar => (fy - center).abs() <= max / 6.0,
+                    Shape::Ring => {
+                        let distance = (fx - center).powi(2) + (fy - center).powi(2);
+                        distance <= (center + 0.5).powi(2) && distance >= (center - 1.5).powi(2)
+                    }
+                };
+                if inside {
+                    points.push((x, y));
+                }
+            }
+        }
+        points
+    }
+}
+
+///
+/// Look of the game: palette, font, sprites, grid and HUD
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub background: ThemeColor,             // Background color
+    pub snake: ThemeColor,                  // Snake color, when drawn without sprites
+    pub food: ThemeColor,                   // Food color, when drawn without sprites
+    pub wall: ThemeColor,                   // Walls at the edge and on the maps
+    pub power_up: ThemeColor,               // Power-ups, when drawn without sprites
+    pub text: ThemeColor,                   // Menu and HUD text
+    pub text_highlight: ThemeColor,         // Hovered menu entries
+    pub text_disabled: ThemeColor,          // Disabled menu entries
+    pub font: String,                       // Font file
+    pub font_size: u16,                     // Font size in points
+    pub sprites: Option<String>,            // Sprite directory, flat colors if None
+    pub grid_lines: Option<ThemeColor>,     // Color of the grid lines, no lines if None
+    pub hud_background: Option<ThemeColor>, // Background of the status text
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            name: String::from("Classic"),
+            background: ThemeColor(0, 0, 0, 255),
+            snake: ThemeColor(0, 255, 0, 255),
+            food: ThemeColor(255, 0, 0, 255),
+            wall: ThemeColor(128, 128, 128, 255),
+            power_up: ThemeColor(0, 200, 255, 255),
+            text: ThemeColor(255, 255, 255, 200),
+            text_highlight: ThemeColor(255, 255, 90, 200),
+            text_disabled: ThemeColor(90, 90, 90, 200),
+            font: String::from("resources/COUR.TTF"),
+            font_size: 20,
+            sprites: Some(String::from("resources")),
+            grid_lines: None,
+            hud_background: None,
+        }
+    }
+}
+
+impl Theme {
+    pub fn load(filename: &Path) -> Result<Theme, String> {
+        let json = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", filename.display(), e))
+    }
+
+    ///
+    /// Every theme of the themes directory, sorted by name
+    /// Falls back to the default theme if none can be loaded
+    ///
+    pub fn all() -> Vec<Theme> {
+        let mut themes: Vec<Theme> = std::fs::read_dir(THEMES_DIR)
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|path| {
+                        Theme::load(&path)
+                            .map_err(|e| println!("Theme: Failed to load {}", e))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if themes.is_empty() {
+            themes.push(Theme::default());
+        }
+        themes.sort_by(|a, b| a.name.cmp(&b.name));
+        themes
+    }
+
+    /// Find a theme by name, the default theme if it doesn't exist
+    pub fn find(name: &str) -> Theme {
+        Theme::all()
+            .into_iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or_default()
+    }
+
+    ///
+    /// Adapt the theme to a color vision deficiency
+    /// Sprites are disabled so the flat palette colors are used
+    ///
+    pub fn with_color_vision(mut self, color_vision: ColorVision) -> Theme {
+        if let Some((snake, food)) = color_vision.palette() {
+            self.snake = snake;
+            self.food = food;
+            self.power_up = ThemeColor(204, 121, 167, 255);
+            self.sprites = None;
+            if color_vision == ColorVision::Monochrome {
+                self.background = ThemeColor(0, 0, 0, 255);
+                self.wall = ThemeColor(160, 160, 160, 255);
+                self.power_up = ThemeColor(210, 210, 210, 255);
+                self.text = ThemeColor(255, 255, 255, 255);
+                self.text_highlight = ThemeColor(255, 255, 255, 255);
+                self.text_disabled = ThemeColor(110, 110, 110, 255);
+            }
+        }
+        self
+    }
+
+    /// Path of a sprite of the theme, None if the theme has no such file
+    pub fn sprite(&self, filename: &str) -> Option<String> {
+        let path = Path::new(self.sprites.as_ref()?).join(filename);
+        path.exists().then(|| path.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::food::FoodTypes;
+    use crate::powerup::PowerUpKind;
+
+    use super::*;
+
+    #[test]
+    fn test_theme_color() {
+        assert_eq!(
+            ThemeColor::try_from(String::from("#ff8000")),
+            Ok(ThemeColor(255, 128, 0, 255))
+        );
+        assert_eq!(
+            ThemeColor::try_from(String::from("#0000ff80")),
+            Ok(ThemeColor(0, 0, 255, 128))
+        );
+        assert!(ThemeColor::try_from(String::from("ff8000")).is_err());
+        assert!(ThemeColor::try_from(String::from("#ff80")).is_err());
+        assert!(ThemeColor::try_from(String::from("#gg8000")).is_err());
+        assert_eq!(
+            String::from(ThemeColor(255, 128, 0, 255)),
+            String::from("#ff8000ff")
+        );
+    }
+
+    #[test]
+    fn test_builtin_themes() {
+        let themes = Theme::all();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Classic", "Game Boy", "High Contrast"]);
+        for theme in &themes {
+            assert!(Path::new(&theme.font).exists());
+            if theme.sprites.is_none() {
+                continue;
+            }
+            let textures = FoodTypes::load_default()
+                .all()
+                .iter()
+                .map(|food| food.texture.clone())
+                .chain(PowerUpKind::all().iter().map(|p| p.texture().to_string()))
+                .collect::<Vec<String>>();
+            assert!(theme.sprite("snake.png").is_some());
+            for texture in textures {
+                assert!(theme.sprite(&texture).is_some(), "{}", texture);
+            }
+        }
+        assert_eq!(Theme::find("Classic"), Theme::default());
+        assert_eq!(Theme::find("Missing"), Theme::default());
+    }
+
+    #[test]
+    fn test_missing_sprite() {
+        let theme = Theme::default();
+        assert!(theme.sprite("snake.png").is_some());
+        assert_eq!(theme.sprite("missing.png"), None);
+    }
+
+    #[test]
+    fn test_color_vision() {
+        let theme = Theme::default().with_color_vision(ColorVision::Normal);
+        assert_eq!(theme, Theme::default());
+        for color_vision in ColorVision::all().into_iter().skip(1) {
+            let theme = Theme::default().with_color_vision(color_vision);
+            assert_eq!(theme.sprites, None);
+            assert_ne!(theme.snake, theme.food);
+        }
+    }
+
+    #[test]
+    fn test_shape_mask() {
+        let shapes = [
+            Shape::Circle,
+            Shape::Triangle,
+            Shape::Square,
+            Shape::Diamond,
+            Shape::Cross,
+            Shape::Plus,
+            Shape::Bar,
+            Shape::Ring,
+        ];
+        let masks: Vec<Vec<(i32, i32)>> = shapes.iter().map(|s| s.mask(8)).collect();
+        for (i, mask) in masks.iter().enumerate() {
+            assert!(!mask.is_empty());
+            assert!(mask
+                .iter()
+                .all(|(x, y)| (0..8).contains(x) && (0..8).contains(y)));
+            // Every shape is different from the others
+            for other in masks.iter().skip(i + 1) {
+                assert_ne!(mask, other);
+            }
+        }
+        assert_eq!(Shape::Square.mask(8).len(), 64);
+        assert_eq!(Shape::Cross.mask(8).len(), 16);
+        assert_eq!(Shape::Bar.mask(8).len(), 16);
+    }
+}
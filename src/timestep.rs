@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+///
+/// Fixed timestep accumulator
+///
+/// Real time is added every frame, the simulation then consumes it one tick
+/// at a time, so the game runs at the same pace whatever the frame rate.
+///
+#[derive(Debug)]
+pub struct FixedTimestep {
+    accumulator: Duration, // Time not yet consumed by the simulation
+    max_ticks: u32,        // Maximum number of ticks kept in the accumulator
+}
+
+impl FixedTimestep {
+    pub fn new(max_ticks: u32) -> FixedTimestep {
+        FixedTimestep {
+            accumulator: Duration::ZERO,
+            max_ticks,
+        }
+    }
+
+    /// Add the time elapsed since the last frame
+    ///
+    /// The accumulator is capped so a long stall (window drag, debugger...)
+    /// doesn't trigger a burst of ticks afterwards.
+    pub fn advance(&mut self, elapsed: Duration, tick: Duration) {
+        self.accumulator = (self.accumulator + elapsed).min(tick * self.max_ticks);
+    }
+
+    /// Consume one tick if enough time is available
+    pub fn consume(&mut self, tick: Duration) -> bool {
+        if tick.is_zero() || self.accumulator < tick {
+            return false;
+        }
+        self.accumulator -= tick;
+        true
+    }
+
+    /// Fraction of the next tick already elapsed, between 0.0 and 1.0
+    pub fn alpha(&self, tick: Duration) -> f32 {
+        if tick.is_zero() {
+            return 0.0;
+        }
+        (self.accumulator.as_secs_f32() / tick.as_secs_f32()).min(1.0)
+    }
+
+    /// Drop the pending time
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_consume() {
+        let tick = Duration::from_millis(100);
+        let mut timestep = FixedTimestep::new(5);
+        timestep.advance(Duration::from_millis(250), tick);
+        assert!(timestep.consume(tick));
+        assert!(timestep.consume(tick));
+        assert!(!timestep.consume(tick));
+        assert!((timestep.alpha(tick) - 0.5).abs() < 0.001);
+        timestep.advance(Duration::from_millis(50), tick);
+        assert!(timestep.consume(tick));
+        assert_eq!(timestep.alpha(tick), 0.0);
+    }
+
+    #[test]
+    fn test_max_ticks() {
+        let tick = Duration::from_millis(100);
+        let mut timestep = FixedTimestep::new(3);
+        timestep.advance(Duration::from_secs(10), tick);
+        let mut ticks = 0;
+        while timestep.consume(tick) {
+            ticks += 1;
+        }
+        assert_eq!(ticks, 3);
+    }
+
+    #[test]
+    fn test_zero_tick() {
+        let mut timestep = FixedTimestep::new(3);
+        timestep.advance(Duration::from_millis(100), Duration::ZERO);
+        assert!(!timestep.consume(Duration::ZERO));
+        assert_eq!(timestep.alpha(Duration::ZERO), 0.0);
+    }
+}
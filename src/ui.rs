@@ -0,0 +1,648 @@
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use snake::input::Action;
+use snake::theme::Theme;
+
+const SPACING: i32 = 10; // Pixels between two rows of a column
+const PADDING: i32 = 8; // Pixels between the frame of a widget and its text
+
+///
+/// Draw a line of text, returns the area it covers
+///
+pub fn draw_text(
+    canvas: &mut WindowCanvas,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    text: &str,
+    x: i32,
+    y: i32,
+    color: Color,
+) -> Result<Rect, String> {
+    // SDL_ttf refuses to render an empty line
+    if text.is_empty() {
+        return Ok(Rect::new(x, y, 0, 0));
+    }
+    let surface = font
+        .render(text)
+        .blended(color)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    let rect = Rect::new(x, y, surface.width(), surface.height());
+    canvas.copy(&texture, None, rect)?;
+    Ok(rect)
+}
+
+///
+/// What a widget shows and does
+///
+/// Interactive widgets keep their value, and turn every change into an
+/// action of the screen through their callback.
+///
+enum Kind<A> {
+    Label,
+    Button(A),
+    Toggle {
+        value: bool,
+        on_change: Box<dyn Fn(bool) -> A>,
+    },
+    Slider {
+        value: i32,
+        min: i32,
+        max: i32,
+        step: i32,
+        on_change: Box<dyn Fn(i32) -> A>,
+    },
+    TextInput {
+        value: String,
+        max_len: usize,
+        on_submit: Box<dyn Fn(&str) -> A>,
+    },
+    List {
+        items: Vec<String>,
+        selected: usize,
+        on_change: Box<dyn Fn(usize) -> A>,
+    },
+}
+
+struct Widget<A> {
+    text: String,
+    kind: Kind<A>,
+    enabled: bool,
+    rect: Rect,
+}
+
+impl<A> Widget<A> {
+    fn focusable(&self) -> bool {
+        self.enabled && !matches!(self.kind, Kind::Label)
+    }
+
+    /// Text drawn for the widget, with its value
+    fn display(&self, focused: bool) -> String {
+        match &self.kind {
+            Kind::Label | Kind::Button(_) => self.text.clone(),
+            Kind::Toggle { value, .. } => {
+                format!("{}: {}", self.text, if *value { "On" } else { "Off" })
+            }
+            Kind::Slider { value, .. } => format!("{}: {}", self.text, value),
+            Kind::TextInput { value, .. } => {
+                format!("{}: {}{}", self.text, value, if focused { "_" } else { "" })
+            }
+            Kind::List {
+                items, selected, ..
+            } => match items.get(*selected) {
+                Some(item) => format!("{}: < {} >", self.text, item),
+                None => self.text.clone(),
+            },
+        }
+    }
+
+    /// Right half of a slider, where the value is drawn as a bar
+    fn bar(&self) -> Rect {
+        let width = self.rect.width() / 2;
+        Rect::new(
+            self.rect.right() - width as i32 - PADDING,
+            self.rect.y() + self.rect.height() as i32 / 3,
+            width,
+            self.rect.height() / 3,
+        )
+    }
+}
+
+///
+/// Vertical layout of widgets, with keyboard, controller and mouse navigation
+///
+/// `A` is the type of the actions produced by the widgets, usually an enum
+/// of the screen:
+///
+/// ```ignore
+/// let mut ui = Column::new(100, 100, 300, 50)
+///     .button("New Game", MenuChoice::NewGame)
+///     .toggle("Sound", true, MenuChoice::Sound);
+/// ```
+///
+pub struct Column<A> {
+    x: i32,
+    y: i32,
+    width: u32,
+    row_height: u32,
+    widgets: Vec<Widget<A>>,
+    focus: Option<usize>, // Widget moved to with the keyboard or a controller
+    hover: Option<usize>, // Widget under the mouse
+}
+
+impl<A: Clone> Column<A> {
+    pub fn new(x: i32, y: i32, width: u32, row_height: u32) -> Column<A> {
+        Column {
+            x,
+            y,
+            width,
+            row_height,
+            widgets: Vec::new(),
+            focus: None,
+            hover: None,
+        }
+    }
+
+    fn push(mut self, text: &str, kind: Kind<A>) -> Self {
+        let y = self.y + self.widgets.len() as i32 * (self.row_height as i32 + SPACING);
+        self.widgets.push(Widget {
+            text: text.to_string(),
+            kind,
+            enabled: true,
+            rect: Rect::new(self.x, y, self.width, self.row_height),
+        });
+        if self.focus.is_none() {
+            self.focus = self.step(None, true);
+        }
+        self
+    }
+
+    pub fn label(self, text: &str) -> Self {
+        self.push(text, Kind::Label)
+    }
+
+    pub fn button(self, text: &str, action: A) -> Self {
+        self.push(text, Kind::Button(action))
+    }
+
+    pub fn toggle(self, text: &str, value: bool, on_change: impl Fn(bool) -> A + 'static) -> Self {
+        let on_change = Box::new(on_change);
+        self.push(text, Kind::Toggle { value, on_change })
+    }
+
+    pub fn slider(
+        self,
+        text: &str,
+        value: i32,
+        (min, max, step): (i32, i32, i32),
+        on_change: impl Fn(i32) -> A + 'static,
+    ) -> Self {
+        let kind = Kind::Slider {
+            value: value.clamp(min, max),
+            min,
+            max,
+            step,
+            on_change: Box::new(on_change),
+        };
+        self.push(text, kind)
+    }
+
+    pub fn text_input(
+        self,
+        text: &str,
+        value: &str,
+        max_len: usize,
+        on_submit: impl Fn(&str) -> A + 'static,
+    ) -> Self {
+        let kind = Kind::TextInput {
+            value: value.chars().take(max_len).collect(),
+            max_len,
+            on_submit: Box::new(on_submit),
+        };
+        self.push(text, kind)
+    }
+
+    pub fn list(
+        self,
+        text: &str,
+        items: Vec<String>,
+        selected: usize,
+        on_change: impl Fn(usize) -> A + 'static,
+    ) -> Self {
+        let kind = Kind::List {
+            selected: selected.min(items.len().saturating_sub(1)),
+            items,
+            on_change: Box::new(on_change),
+        };
+        self.push(text, kind)
+    }
+
+    /// Enable or disable the last added widget
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        if let Some(widget) = self.widgets.last_mut() {
+            widget.enabled = enabled;
+        }
+        if !self.focus.is_some_and(|i| self.widgets[i].focusable()) {
+            self.focus = self.step(None, true);
+        }
+        self
+    }
+
+    pub fn focus(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Move the focus to a widget, kept where it is if that widget can't have it
+    pub fn set_focus(&mut self, index: Option<usize>) {
+        if let Some(index) = index.filter(|i| self.widgets.get(*i).is_some_and(|w| w.focusable())) {
+            self.focus = Some(index);
+        }
+    }
+
+    /// Whether typed keys go to a text input instead of the key bindings
+    pub fn editing(&self) -> bool {
+        self.focus
+            .is_some_and(|i| matches!(self.widgets[i].kind, Kind::TextInput { .. }))
+    }
+
+    ///
+    /// Next focusable widget in a direction, wrapping around
+    /// Without focus, starts from the first or last widget
+    ///
+    fn step(&self, from: Option<usize>, forward: bool) -> Option<usize> {
+        let count = self.widgets.len();
+        if count == 0 {
+            return None;
+        }
+        let mut index = from.unwrap_or(if forward { count - 1 } else { 0 });
+        for _ in 0..count {
+            index = if forward {
+                (index + 1) % count
+            } else {
+                (index + count - 1) % count
+            };
+            if self.widgets[index].focusable() {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    fn at(&self, x: i32, y: i32) -> Option<usize> {
+        self.widgets
+            .iter()
+            .position(|w| w.focusable() && w.rect.contains_point((x, y)))
+    }
+
+    ///
+    /// Handle an event, returns the action of the widget it changed
+    ///
+    /// `action` is the action bound to the event by the key bindings or the
+    /// controller: Up and Down move the focus, Left and Right change the value
+    /// and Confirm activates the focused widget.
+    ///
+    pub fn handle_event(&mut self, event: &Event, action: Option<Action>) -> Option<A> {
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                self.hover = self.at(*x, *y);
+                None
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => {
+                let index = self.at(*x, *y)?;
+                self.focus = Some(index);
+                self.click(index, *x)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Tab),
+                keymod,
+                ..
+            } => {
+                let forward = !keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                self.focus = self.step(self.focus, forward).or(self.focus);
+                None
+            }
+            Event::TextInput { text, .. } if self.editing() => {
+                self.type_text(text);
+                None
+            }
+            Event::KeyDown {
+                keycode: Some(key), ..
+            } if self.editing() => match *key {
+                Keycode::Backspace => {
+                    self.type_text("\u{8}");
+                    None
+                }
+                // Letters typed in the text aren't actions
+                Keycode::Up | Keycode::Down | Keycode::Return | Keycode::KpEnter => {
+                    self.act(action?)
+                }
+                _ => None,
+            },
+            _ => self.act(action?),
+        }
+    }
+
+    fn act(&mut self, action: Action) -> Option<A> {
+        match action {
+            Action::Up | Action::Down => {
+                self.focus = self.step(self.focus, action == Action::Down).or(self.focus);
+                None
+            }
+            Action::Left => self.adjust(self.focus?, -1),
+            Action::Right => self.adjust(self.focus?, 1),
+            Action::Confirm => self.activate(self.focus?),
+            _ => None,
+        }
+    }
+
+    /// Append typed text to the focused text input, a backspace removes a character
+    fn type_text(&mut self, text: &str) {
+        let Some(index) = self.focus else { return };
+        if let Kind::TextInput { value, max_len, .. } = &mut self.widgets[index].kind {
+            for c in text.chars() {
+                if c == '\u{8}' {
+                    value.pop();
+                } else if !c.is_control() && value.chars().count() < *max_len {
+                    value.push(c);
+                }
+            }
+        }
+    }
+
+    fn activate(&mut self, index: usize) -> Option<A> {
+        match &mut self.widgets[index].kind {
+            Kind::Button(action) => Some(action.clone()),
+            Kind::TextInput {
+                value, on_submit, ..
+            } => Some(on_submit(value)),
+            Kind::Toggle { .. } | Kind::List { .. } => self.adjust(index, 1),
+            Kind::Label | Kind::Slider { .. } => None,
+        }
+    }
+
+    fn adjust(&mut self, index: usize, delta: i32) -> Option<A> {
+        match &mut self.widgets[index].kind {
+            Kind::Toggle { value, on_change } => {
+                *value = !*value;
+                Some(on_change(*value))
+            }
+            Kind::Slider {
+                value,
+                min,
+                max,
+                step,
+                on_change,
+            } => {
+                let changed = (*value + delta * *step).clamp(*min, *max);
+                if changed == *value {
+                    return None;
+                }
+                *value = changed;
+                Some(on_change(changed))
+            }
+            Kind::List {
+                items,
+                selected,
+                on_change,
+            } => {
+                if items.is_empty() {
+                    return None;
+                }
+                let count = items.len() as i32;
+                *selected = (*selected as i32 + delta).rem_euclid(count) as usize;
+                Some(on_change(*selected))
+            }
+            _ => None,
+        }
+    }
+
+    fn click(&mut self, index: usize, x: i32) -> Option<A> {
+        let bar = self.widgets[index].bar();
+        match &mut self.widgets[index].kind {
+            // Clicking the bar of a slider jumps to the value under the mouse
+            Kind::Slider {
+                value,
+                min,
+                max,
+                step,
+                on_change,
+            } if x >= bar.x() => {
+                let ratio = (x - bar.x()) as f32 / bar.width() as f32;
+                let steps = ((*max - *min) as f32 * ratio / *step as f32).round() as i32;
+                let changed = (*min + steps * *step).clamp(*min, *max);
+                if changed == *value {
+                    return None;
+                }
+                *value = changed;
+                Some(on_change(changed))
+            }
+            _ => self.activate(index),
+        }
+    }
+
+    ///
+    /// Draw the widgets: the focused one is framed, the hovered one highlighted
+    ///
+    pub fn draw(
+        &self,
+        canvas: &mut WindowCanvas,
+        font: &Font,
+        texture_creator: &TextureCreator<WindowContext>,
+        theme: &Theme,
+    ) -> Result<(), String> {
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let focused = self.focus == Some(i);
+            let color = if !widget.enabled {
+                theme.text_disabled
+            } else if self.hover == Some(i) {
+                theme.text_highlight
+            } else {
+                theme.text
+            };
+
+            if focused {
+                canvas.set_draw_color(theme.text_highlight);
+                canvas.draw_rect(widget.rect)?;
+            }
+            if let Kind::Slider {
+                value, min, max, ..
+            } = widget.kind
+            {
+                let bar = widget.bar();
+                let filled = (bar.width() as i32 * (value - min) / (max - min).max(1)) as u32;
+                canvas.set_draw_color(color);
+                canvas.draw_rect(bar)?;
+                if filled > 0 {
+                    canvas.fill_rect(Rect::new(bar.x(), bar.y(), filled, bar.height()))?;
+                }
+            }
+
+            let y = widget.rect.y() + (widget.rect.height() as i32 - font.height()) / 2;
+            draw_text(
+                canvas,
+                font,
+                texture_creator,
+                &widget.display(focused),
+                widget.rect.x() + PADDING,
+                y,
+                color.into(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Choice {
+        Play,
+        Quit,
+        Sound(bool),
+        Volume(i32),
+        Name(String),
+        Theme(usize),
+    }
+
+    fn column() -> Column<Choice> {
+        Column::new(0, 0, 400, 40)
+            .label("Title")
+            .button("Play", Choice::Play)
+            .button("Continue", Choice::Play)
+            .enabled(false)
+            .toggle("Sound", true, Choice::Sound)
+            .slider("Volume", 50, (0, 100, 10), Choice::Volume)
+            .text_input("Name", "Bob", 5, |name| Choice::Name(name.to_string()))
+            .list(
+                "Theme",
+                vec![String::from("A"), String::from("B")],
+                0,
+                Choice::Theme,
+            )
+            .button("Quit", Choice::Quit)
+    }
+
+    fn key(keycode: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    fn click(x: i32, y: i32) -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn test_focus() {
+        let mut ui = column();
+        // Labels and disabled widgets are skipped
+        assert_eq!(ui.focus(), Some(1));
+        ui.handle_event(&key(Keycode::Down), Some(Action::Down));
+        assert_eq!(ui.focus(), Some(3));
+        ui.handle_event(&key(Keycode::Tab), None);
+        assert_eq!(ui.focus(), Some(4));
+        ui.handle_event(&key(Keycode::Up), Some(Action::Up));
+        ui.handle_event(&key(Keycode::Up), Some(Action::Up));
+        ui.handle_event(&key(Keycode::Up), Some(Action::Up));
+        // Wraps around
+        assert_eq!(ui.focus(), Some(7));
+        ui.set_focus(Some(2));
+        assert_eq!(ui.focus(), Some(7));
+    }
+
+    #[test]
+    fn test_values() {
+        let mut ui = column();
+        let confirm = key(Keycode::Return);
+        assert_eq!(
+            ui.handle_event(&confirm, Some(Action::Confirm)),
+            Some(Choice::Play)
+        );
+        ui.set_focus(Some(3));
+        assert_eq!(
+            ui.handle_event(&confirm, Some(Action::Confirm)),
+            Some(Choice::Sound(false))
+        );
+        ui.set_focus(Some(4));
+        let right = key(Keycode::Right);
+        assert_eq!(
+            ui.handle_event(&right, Some(Action::Right)),
+            Some(Choice::Volume(60))
+        );
+        ui.set_focus(Some(6));
+        assert_eq!(
+            ui.handle_event(&right, Some(Action::Right)),
+            Some(Choice::Theme(1))
+        );
+        assert_eq!(
+            ui.handle_event(&right, Some(Action::Right)),
+            Some(Choice::Theme(0))
+        );
+    }
+
+    #[test]
+    fn test_slider_bounds() {
+        let mut ui = column();
+        ui.set_focus(Some(4));
+        let left = key(Keycode::Left);
+        for _ in 0..5 {
+            ui.handle_event(&left, Some(Action::Left));
+        }
+        assert_eq!(ui.handle_event(&left, Some(Action::Left)), None);
+        // The bar fills the right half of the row, minus the padding
+        let row = 4 * (40 + SPACING) + 20;
+        assert_eq!(
+            ui.handle_event(&click(392, row), None),
+            Some(Choice::Volume(100))
+        );
+        assert_eq!(
+            ui.handle_event(&click(292, row), None),
+            Some(Choice::Volume(50))
+        );
+    }
+
+    #[test]
+    fn test_text_input() {
+        let mut ui = column();
+        ui.set_focus(Some(5));
+        assert!(ui.editing());
+        let text = |text: &str| Event::TextInput {
+            timestamp: 0,
+            window_id: 0,
+            text: text.to_string(),
+        };
+        // Bound keys are typed instead of moving the focus
+        assert_eq!(ui.handle_event(&key(Keycode::W), Some(Action::Up)), None);
+        ui.handle_event(&text("by"), None);
+        ui.handle_event(&text("xyz"), None);
+        ui.handle_event(&key(Keycode::Backspace), None);
+        assert_eq!(ui.focus(), Some(5));
+        assert_eq!(
+            ui.handle_event(&key(Keycode::Return), Some(Action::Confirm)),
+            Some(Choice::Name(String::from("Bobb")))
+        );
+    }
+
+    #[test]
+    fn test_click() {
+        let mut ui = column();
+        let quit = 7 * (40 + SPACING) + 5;
+        assert_eq!(ui.handle_event(&click(10, quit), None), Some(Choice::Quit));
+        assert_eq!(ui.focus(), Some(7));
+        // Disabled button
+        assert_eq!(
+            ui.handle_event(&click(10, 2 * (40 + SPACING) + 5), None),
+            None
+        );
+        assert_eq!(ui.focus(), Some(7));
+    }
+}